@@ -0,0 +1,76 @@
+// Deployed at the Jupiter v6 address on localnet (see Anchor.toml) so the
+// InitializeSwap -> Swap -> Finalize transaction can run end to end without
+// real liquidity. It settles a SharedAccountsRoute at the quoted rate less the
+// platform fee: the input goes into the program's source account and the rest
+// of the quoted output is paid out of the program's destination account,
+// which the tests pre-fund. A platform fee is how the tests make a swap
+// deliver short of its quote. A SharedAccountsExactOutRoute takes exactly its
+// quoted input and pays exactly its output.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+const SHARED_ACCOUNTS_ROUTE: [u8; 8] = [0xc1, 0x20, 0x9b, 0x33, 0x41, 0xd6, 0x9c, 0x81];
+const SHARED_ACCOUNTS_EXACT_OUT_ROUTE: [u8; 8] = [0xb0, 0xd1, 0x69, 0xa8, 0x9a, 0x7d, 0x45, 0x3e];
+
+// Two amounts, slippage_bps and platform_fee_bps close every route: in_amount
+// and quoted_out_amount, or out_amount and quoted_in_amount for exact-out
+const ROUTE_TAIL: usize = 8 + 8 + 2 + 1;
+
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    if data.len() < 8 + 1 + ROUTE_TAIL {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let id = data[8];
+    let tail = &data[data.len() - ROUTE_TAIL..];
+    let first = u64::from_le_bytes(tail[..8].try_into().unwrap());
+    let second = u64::from_le_bytes(tail[8..16].try_into().unwrap());
+    let platform_fee_bps = tail[18] as u64;
+    let (in_amount, out_amount) = match data[..8].try_into().unwrap() {
+        SHARED_ACCOUNTS_ROUTE => (first, second - second * platform_fee_bps / 10_000),
+        SHARED_ACCOUNTS_EXACT_OUT_ROUTE => (second, first),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    let [token_program, program_authority, user_transfer_authority, source_token, program_source_token, program_destination_token, destination_token, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let (authority, bump) = Pubkey::find_program_address(&[b"authority", &[id]], program_id);
+    if authority != *program_authority.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            source_token.key,
+            program_source_token.key,
+            user_transfer_authority.key,
+            &[],
+            in_amount,
+        )?,
+        &[source_token.clone(), program_source_token.clone(), user_transfer_authority.clone(), token_program.clone()],
+    )?;
+
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key,
+            program_destination_token.key,
+            destination_token.key,
+            program_authority.key,
+            &[],
+            out_amount,
+        )?,
+        &[program_destination_token.clone(), destination_token.clone(), program_authority.clone(), token_program.clone()],
+        &[&[b"authority", &[id], &[bump]]],
+    )
+}
@@ -0,0 +1,49 @@
+// Writes the data of the Jupiter v6 instruction of a mainnet transaction to a
+// fixture file, as hex, for the decoder tests in src/programs.rs.
+//
+//   npx ts-node programs/novi/fixtures/jupiter/capture.ts <signature> <file>
+//
+// RPC_URL picks the node, mainnet-beta by default.
+
+import { Connection, PublicKey } from "@solana/web3.js";
+import * as fs from "fs";
+import * as path from "path";
+
+const JUPITER_PROGRAM_ID = new PublicKey(
+  "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"
+);
+
+const main = async ([signature, file]: string[]) => {
+  if (!signature || !file) {
+    throw new Error("usage: capture.ts <signature> <file>");
+  }
+  const connection = new Connection(
+    process.env.RPC_URL ?? "https://api.mainnet-beta.solana.com"
+  );
+  const tx = await connection.getTransaction(signature, {
+    maxSupportedTransactionVersion: 0,
+  });
+  if (!tx) {
+    throw new Error(`${signature} not found`);
+  }
+
+  // Versioned transactions load part of their accounts from lookup tables
+  const keys = tx.transaction.message.getAccountKeys({
+    accountKeysFromLookups: tx.meta?.loadedAddresses,
+  });
+  const swaps = tx.transaction.message.compiledInstructions.filter((ix) =>
+    keys.get(ix.programIdIndex)?.equals(JUPITER_PROGRAM_ID)
+  );
+  if (swaps.length !== 1) {
+    throw new Error(`expected one Jupiter instruction, found ${swaps.length}`);
+  }
+
+  const data = Buffer.from(swaps[0].data);
+  fs.writeFileSync(path.resolve(__dirname, file), data.toString("hex") + "\n");
+  console.log(`wrote ${file} from ${signature}, slot ${tx.slot}`);
+};
+
+main(process.argv.slice(2)).catch((err) => {
+  console.error(err);
+  process.exit(1);
+});
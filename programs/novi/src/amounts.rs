@@ -0,0 +1,89 @@
+use std::result::Result as StdResult;
+
+use crate::errors::NoviError;
+
+// An amount in whole tokens, the way a user types it in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UiAmount(pub u64);
+
+// An amount in the smallest unit of a Mint, the way the Token Program counts it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BaseAmount(pub u64);
+
+// How many base units make up one whole token: 10^decimals
+pub fn unit(decimals: u8) -> StdResult<u64, NoviError> {
+    10u64.checked_pow(decimals.into()).ok_or(NoviError::Overflow)
+}
+
+// a * b / c without intermediate overflow
+pub fn mul_div(a: u64, b: u64, c: u64, round_up: bool) -> StdResult<u64, NoviError> {
+    let numerator = (a as u128).checked_mul(b as u128).ok_or(NoviError::Overflow)?;
+    let mut result = numerator.checked_div(c as u128).ok_or(NoviError::Overflow)?;
+    if round_up && numerator % c as u128 != 0 {
+        result += 1;
+    }
+    u64::try_from(result).map_err(|_| NoviError::Overflow)
+}
+
+impl UiAmount {
+    pub fn to_base(self, decimals: u8) -> StdResult<BaseAmount, NoviError> {
+        self.0.checked_mul(unit(decimals)?).map(BaseAmount).ok_or(NoviError::Overflow)
+    }
+}
+
+impl BaseAmount {
+    // Rounds down to the last whole token
+    pub fn to_ui(self, decimals: u8) -> StdResult<UiAmount, NoviError> {
+        Ok(UiAmount(self.0 / unit(decimals)?))
+    }
+
+    // Moves an amount between Mints with different decimals, rounding down
+    pub fn rescale(self, from_decimals: u8, to_decimals: u8) -> StdResult<BaseAmount, NoviError> {
+        if to_decimals >= from_decimals {
+            self.0.checked_mul(unit(to_decimals - from_decimals)?).map(BaseAmount).ok_or(NoviError::Overflow)
+        } else {
+            Ok(BaseAmount(self.0 / unit(from_decimals - to_decimals)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_is_a_power_of_ten() {
+        assert_eq!(unit(0).unwrap(), 1);
+        assert_eq!(unit(6).unwrap(), 1_000_000);
+        assert_eq!(unit(9).unwrap(), 1_000_000_000);
+        assert!(matches!(unit(20), Err(NoviError::Overflow)));
+    }
+
+    #[test]
+    fn zero_decimals_is_the_identity() {
+        assert_eq!(UiAmount(42).to_base(0).unwrap(), BaseAmount(42));
+        assert_eq!(BaseAmount(42).to_ui(0).unwrap(), UiAmount(42));
+        assert_eq!(BaseAmount(42).rescale(0, 0).unwrap(), BaseAmount(42));
+    }
+
+    #[test]
+    fn six_decimals_scales_by_a_million() {
+        assert_eq!(UiAmount(25).to_base(6).unwrap(), BaseAmount(25_000_000));
+        assert_eq!(BaseAmount(25_999_999).to_ui(6).unwrap(), UiAmount(25));
+        assert_eq!(BaseAmount(1_500_000).rescale(6, 0).unwrap(), BaseAmount(1));
+    }
+
+    #[test]
+    fn nine_decimals_scales_by_a_billion() {
+        assert_eq!(UiAmount(2).to_base(9).unwrap(), BaseAmount(2_000_000_000));
+        assert_eq!(BaseAmount(2_000_000_001).to_ui(9).unwrap(), UiAmount(2));
+        assert!(matches!(UiAmount(u64::MAX / 1_000_000_000 + 1).to_base(9), Err(NoviError::Overflow)));
+    }
+
+    #[test]
+    fn rescale_between_six_and_nine_decimals() {
+        assert_eq!(BaseAmount(1_234_567).rescale(6, 9).unwrap(), BaseAmount(1_234_567_000));
+        assert_eq!(BaseAmount(1_234_567_891).rescale(9, 6).unwrap(), BaseAmount(1_234_567));
+        assert!(matches!(BaseAmount(u64::MAX).rescale(6, 9), Err(NoviError::Overflow)));
+    }
+}
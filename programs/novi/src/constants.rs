@@ -1,15 +1,18 @@
-pub const MIN_SOL_THRESHOLD: u64 = 0;
-pub const MAX_SOL_THRESHOLD: u64 = 1_000_000_000_000_000;
-pub const MIN_USD_THRESHOLD: u64 = 0;
-pub const MAX_USD_THRESHOLD: u64 = 1_000_000_000_000_000;
+pub const TOTAL_WEIGHT_BPS: u64 = 10_000;
+pub const SHARE_DECIMALS: u8 = 6;
+pub const DEFAULT_REFUND_DELAY_SLOTS: u64 = 216_000;
+pub const DEFAULT_MIN_KEEPER_BOND: u64 = 1_000_000_000;
+pub const DEFAULT_MAX_SLIPPAGE_BPS: u16 = 50;
+pub const DEFAULT_MAX_HOPS: u8 = 4;
+pub const DEFAULT_PRICE_BAND_BPS: u16 = 100;
+pub const DEFAULT_MAX_PRICE_AGE_SECS: u32 = 60;
+pub const DEFAULT_MAX_CONFIDENCE_BPS: u16 = 50;
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+// Decimals of NAV values, in the quote currency of the Oracles
+pub const NAV_DECIMALS: u8 = 6;
 
 use anchor_lang::declare_id;
 
-pub mod admin {
-    use super::*;
-    declare_id!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"); // ToDo
-}
-
 pub mod usdc {
     use super::*;
     declare_id!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
@@ -7,8 +7,19 @@ pub enum NoviError {
     #[msg("Underflow")]
     Underflow,
 
+    #[msg("InitializeConfig Instruction: Only the upgrade authority can initialize the Config")]
+    InvalidUpgradeAuthority,
+    #[msg("AcceptAdmin Instruction: You're not the pending Admin")]
+    NotPendingAdmin,
+    #[msg("SetDepositMint Instruction: The minimum Threshold is above the maximum Threshold")]
+    InvalidThresholds,
+
     #[msg("CreateIndex Instruction: You don't have the power to do that")]
     PrivilageEscalated,
+    #[msg("CreateIndex Instruction: There must be exactly one Weight per Mint")]
+    WeightsLengthMismatch,
+    #[msg("CreateIndex Instruction: The Weights must be non-zero and add up to 10_000 bps")]
+    InvalidWeights,
 
     #[msg("Deposit Instruction: You surpassed the maximum Threshold")]
     MaxThreshold,
@@ -16,6 +27,8 @@ pub enum NoviError {
     MinThreshold,
     #[msg("Deposit Instruction: You're not using an allowed Mint")]
     InvalidMint,
+    #[msg("Deposit Instruction: Only native SOL can be deposited without a Token Account")]
+    MissingUserToken,
     
     #[msg("InitializeSwap Instruction: You already swapped this token")]
     AlreadySwapped,
@@ -23,7 +36,7 @@ pub enum NoviError {
     CpiDisabled,
     #[msg("InitializeSwap Instruction: There is no Swap Instruction after this instruction")]
     InvalidSwapIx,
-    #[msg("InitializeSwap Instruction: The Swap Instruction has more than 50bps of slippage")]
+    #[msg("InitializeSwap Instruction: The Swap Instruction has more slippage than allowed")]
     InvalidSlippage,
     #[msg("InitializeSwap Instruction: The Swap Instruction has the wrong amount")]
     InvalidAmount,
@@ -33,20 +46,121 @@ pub enum NoviError {
     InvalidToMint,
     #[msg("InitializeSwap Instruction: The Swap Instruction is missing")]
     MissingSwapIx,
+    #[msg("InitializeSwap Instruction: The Swap Instruction doesn't spend from the Swapper's Token Account")]
+    InvalidSwapSource,
+    #[msg("InitializeSwap Instruction: The Swap Instruction doesn't pay into the Token Account Finalize pulls from")]
+    InvalidSwapDestination,
+    #[msg("InitializeSwap Instruction: The Oracle price accounts are missing or don't match the Index")]
+    MissingOracle,
+    #[msg("InitializeSwap Instruction: The Token Ledger of the Swap Instruction is missing or invalid")]
+    InvalidTokenLedger,
     #[msg("InitializeSwap Instruction:  There is no Finalize Instruction after the Swap Instruction")]
     InvalidFinalizeIx,
-    #[msg("InitializeSwap Instruction: The Finalize Instruction is passing in the wrong amount")]
-    InvalidFinalizeAmount,
     #[msg("InitializeSwap Instruction: The Finalize Instruction is missing")]
     MissingFinalizeIx,
     #[msg("InitializeSwap Instruction: The Finalize Instruction has the wrong Owner")]
     InvalidFinalizeOwner,
     #[msg("InitializeSwap Instruction: The Finalize Instruction has the wrong Mint Address")]
     InvalidFinalizeMint,
+    #[msg("InitializeSwap Instruction: The Finalize Instruction has the wrong Deposit")]
+    InvalidFinalizeDeposit,
+    #[msg("InitializeSwap Instruction: The Finalize Instruction pulls from the wrong Token Account")]
+    InvalidFinalizeSwapperToken,
+
+    #[msg("Finalize Instruction: Only the Keeper that opened the Swap Ticket can finalize it")]
+    NotTicketKeeper,
+    #[msg("Finalize Instruction: A leg delivered below the price band has to open its Swap Dispute")]
+    MissingDispute,
+    #[msg("Finalize Instruction: Only a leg delivered below the price band opens a Swap Dispute")]
+    UnwarrantedDispute,
+
+    #[msg("Redeem Instruction: You don't have enough of this Mint in your Index Profile")]
+    InsufficientProfileBalance,
+
+    #[msg("InitializeRedeemSwap Instruction: There is no FinalizeRedeem Instruction after the Swap Instruction")]
+    InvalidFinalizeRedeemIx,
+    #[msg("InitializeRedeemSwap Instruction: The FinalizeRedeem Instruction is passing in the wrong amount")]
+    InvalidFinalizeRedeemAmount,
+    #[msg("InitializeRedeemSwap Instruction: The FinalizeRedeem Instruction is missing")]
+    MissingFinalizeRedeemIx,
+    #[msg("InitializeRedeemSwap Instruction: The FinalizeRedeem Instruction has the wrong Owner")]
+    InvalidFinalizeRedeemOwner,
+    #[msg("InitializeRedeemSwap Instruction: The FinalizeRedeem Instruction has the wrong Mint Address")]
+    InvalidFinalizeRedeemMint,
+    #[msg("InitializeRedeemSwap Instruction: The FinalizeRedeem Instruction pays out of the wrong Token Account")]
+    InvalidFinalizeRedeemSwapperToken,
+
+    #[msg("GetNav Instruction: The Index has no Oracles to price it with")]
+    NoOracles,
+    #[msg("GetNav Instruction: Pass the Mint, Vault and Oracle of every Mint of the Index, in order")]
+    InvalidNavAccounts,
 
     #[msg("Deposit Account >> Deposit: Amounts do not match")]
     AmountMismatch,
+    #[msg("Deposit Account >> CheckRefundable: The Deposit can't be refunded yet")]
+    RefundLocked,
+    #[msg("Deposit Account >> FinalizeLeg: This leg was already finalized")]
+    AlreadyFinalized,
+
+    #[msg("Swap Ticket >> SwappedAmount: The Swap delivered less than the quote minus slippage")]
+    InsufficientSwapOutput,
+
+    #[msg("Pause State >> CheckInflows: Deposits and Swaps are paused, only Redemptions are allowed")]
+    RedeemOnly,
+    #[msg("Pause State >> CheckOutflows: The Protocol is paused")]
+    Paused,
+
+    #[msg("Config Account >> CheckRole: You don't have the Role required to do that")]
+    MissingRole,
 
     #[msg("Index Account >> CheckAddress: The Mint passed in is Invalid")]
     InvalidMintAddress,
+
+    #[msg("Index Account >> CheckRoute: The Swap route has more hops than allowed")]
+    RouteTooLong,
+    #[msg("Index Account >> CheckRoute: The Swap routes through an AMM that isn't allowed")]
+    AmmNotAllowed,
+    #[msg("Index Account >> CheckRoute: The Swap route plan is inconsistent")]
+    InvalidRoutePlan,
+    #[msg("Index Account >> SetAmmAllowlist: AMMs have to be known and listed once, and at least one hop allowed")]
+    InvalidAmmAllowlist,
+
+    #[msg("Index Account >> SetPriceGuard: There has to be one Oracle per Mint, or none at all")]
+    InvalidOracleList,
+
+    #[msg("Oracle Price >> Load: The account isn't a fully verified, positive Oracle price")]
+    InvalidOracle,
+    #[msg("Price Guard >> CheckSettings: The band and the confidence have to be 1 to 10_000 bps, and the max age non-zero")]
+    InvalidPriceGuard,
+    #[msg("Price Guard >> CheckPrice: The Oracle price is too old")]
+    StalePrice,
+    #[msg("Price Guard >> CheckPrice: The Oracle price is too uncertain")]
+    LowConfidencePrice,
+    #[msg("Price Guard >> CheckQuote: The Swap quote is too far from the Oracle price")]
+    QuoteOutsidePriceBand,
+
+    #[msg("Fee Settings: Fees can't exceed 10_000 bps, nor take the whole value of the Shares")]
+    InvalidFees,
+    #[msg("Index Account >> SetFees: A performance fee needs Oracles to value the Index with")]
+    PerformanceFeeWithoutOracles,
+    #[msg("CollectFees Instruction: There are no Fees to collect")]
+    NoFees,
+
+    #[msg("SlashKeeper Instruction: The Keeper didn't swap the leg of this Swap Dispute")]
+    MissingSlashEvidence,
+    #[msg("SlashKeeper Instruction: The slash can't exceed what the Swap fell short of the price band")]
+    SlashExceedsShortfall,
+
+    #[msg("Keeper Account >> CheckBond: The Keeper's bond is too small")]
+    InsufficientBond,
+    #[msg("Keeper Account >> WithdrawBond: The bond is locked until the dispute window after the last Swap is over")]
+    BondLocked,
+
+    #[msg("Keeper Bounty: The cut of a leg can't exceed 10_000 bps")]
+    InvalidKeeperBounty,
+    #[msg("Keeper Bounty: USDC bounties need the bounty escrow and a USDC Token Account, other bounties no escrow")]
+    InvalidBountyAccounts,
+
+    #[msg("Max Slippage: The maximum Slippage can't exceed 10_000 bps")]
+    InvalidMaxSlippage,
 }
\ No newline at end of file
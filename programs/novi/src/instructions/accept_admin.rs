@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    state::Config,
+};
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> AcceptAdmin<'info> {
+    pub fn accept_admin(&mut self) -> Result<()> {
+        require!(self.config.pending_admin == Some(self.pending_admin.key()), NoviError::NotPendingAdmin);
+
+        self.config.admin = self.pending_admin.key();
+        self.config.pending_admin = None;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+
+use crate::state::KeeperAccount;
+
+#[derive(Accounts)]
+pub struct BondKeeper<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = KeeperAccount::INIT_SPACE,
+        seeds = [b"keeper", authority.key().as_ref()],
+        bump,
+    )]
+    pub keeper: Account<'info, KeeperAccount>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> BondKeeper<'info> {
+    pub fn bond_keeper(&mut self, amount: u64, bumps: BondKeeperBumps) -> Result<()> {
+        // Registers the Keeper on its first bond, tops it up after that
+        self.keeper.initialize_if_needed(self.authority.key(), bumps.keeper);
+        self.keeper.add_bond(amount)?;
+
+        transfer(
+            CpiContext::new(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.authority.to_account_info(),
+                    to: self.keeper.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    }
+}
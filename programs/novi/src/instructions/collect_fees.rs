@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{mint_to, Mint, MintTo, Token, TokenAccount}
+};
+
+use crate::{
+    amounts::unit,
+    errors::NoviError,
+    oracle::Nav,
+    state::{Config, FeePayout, IndexAccount, IndexProfile},
+};
+
+// The remaining accounts value the Index (see Nav::compute), only needed with
+// a performance fee.
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        seeds = [b"profile", index.key().as_ref(), recipient.key().as_ref()],
+        bump,
+        space = IndexProfile::space(index.mint_list.len()),
+    )]
+    pub recipient_profile: Account<'info, IndexProfile>,
+
+    #[account(
+        mut,
+        address = index.share_mint,
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = share_mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_shares: Account<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> CollectFees<'info> {
+    pub fn collect_fees(&mut self, payout: FeePayout, legs: &'info [AccountInfo<'info>], bumps: CollectFeesBumps) -> Result<()> {
+        require_keys_eq!(self.recipient.key(), self.index.fees.recipient, NoviError::PrivilageEscalated);
+        self.config.pause_state.max(self.index.pause_state).check_outflows()?;
+
+        // Bring every fee up to date, entry and exit fees are already pending
+        self.index.accrue_management_fee(self.share_mint.supply, Clock::get()?.unix_timestamp)?;
+        if self.index.fees.performance_fee_bps > 0 {
+            let nav = Nav::compute(&self.index, &self.share_mint, legs)?;
+            self.index.accrue_performance_fee(nav.share_value, self.share_mint.supply, unit(self.share_mint.decimals)?)?;
+        }
+
+        let supply = self.index.share_supply(self.share_mint.supply)?;
+        let fee_shares = self.index.take_fee_shares()?;
+
+        match payout {
+            FeePayout::Shares => {
+                let index_bump_slice: &[u8] = &[self.index.bump];
+                let signer_seeds = &[&[b"index".as_ref(), self.index.title.as_bytes(), index_bump_slice][..]];
+                mint_to(
+                    CpiContext::new_with_signer(
+                        self.token_program.to_account_info(),
+                        MintTo {
+                            mint: self.share_mint.to_account_info(),
+                            to: self.recipient_shares.to_account_info(),
+                            authority: self.index.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    fee_shares,
+                )
+            },
+            FeePayout::Vaults => {
+                // Bring the Profile up to date with the Index before crediting it
+                let mints = self.index.mint_list.len();
+                self.recipient_profile.initialize_if_needed(self.recipient.key(), mints, bumps.recipient_profile);
+                IndexProfile::realloc(&mut self.recipient_profile, mints, self.recipient.to_account_info(), self.system_program.to_account_info())?;
+
+                // Release the holdings backing the fee Shares, the recipient
+                // withdraws them like any other Profile balance
+                let released = self.index.unwrap_shares(fee_shares, supply)?;
+                Ok(self.recipient_profile.credit(&released)?)
+            },
+        }
+    }
+}
@@ -1,38 +1,100 @@
 use anchor_lang::prelude::*;
+use anchor_spl::{
+    metadata::{
+        create_metadata_accounts_v3, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3, Metadata
+    },
+    token::{Mint, Token},
+};
 
 use crate::{
-    state::IndexAccount, 
-    constants::admin, 
-    errors::NoviError
+    state::{Config, IndexAccount, Role},
+    constants::SHARE_DECIMALS,
 };
 
 #[derive(Accounts)]
-#[instruction(title: String, mint_list: Vec<Pubkey>)]
+#[instruction(title: String, mint_list: Vec<Pubkey>, weights: Vec<u16>)]
 pub struct CreateIndex<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub curator: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
     #[account(
         init,
         payer = payer,
         seeds = [b"index", title.as_bytes()],
         bump,
-        space = IndexAccount::INIT_SPACE + title.len() + mint_list.len() * 32,
+        space = IndexAccount::space(title.len(), mint_list.len(), 0, 0),
     )]
     pub index: Account<'info, IndexAccount>,
-    
-    pub system_program: Program<'info, System>
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"shares", index.key().as_ref()],
+        bump,
+        mint::decimals = SHARE_DECIMALS,
+        mint::authority = index,
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata_program.key().as_ref(), share_mint.key().as_ref()],
+        bump,
+        seeds::program = token_metadata_program.key(),
+    )]
+    /// CHECK: Initialized by the Token Metadata program
+    pub share_metadata: UncheckedAccount<'info>,
+
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
-impl<'info> CreateIndex<'info> {        
-    pub fn create(&mut self, title: String, mint_list: Vec<Pubkey>, bumps: CreateIndexBumps) -> Result<()> {
-        require!(self.admin.key() == admin::id(), NoviError::PrivilageEscalated);
-        
+impl<'info> CreateIndex<'info> {
+    pub fn create(&mut self, title: String, mint_list: Vec<Pubkey>, weights: Vec<u16>, symbol: String, uri: String, bumps: CreateIndexBumps) -> Result<()> {
+        self.config.check_role(Role::Curator, self.curator.key())?;
+        IndexAccount::check_weights(&mint_list, &weights)?;
+
         let index = &mut self.index;
-        index.initialize(title, mint_list, bumps.index);
+        index.initialize(title, self.curator.key(), mint_list, weights, self.share_mint.key(), bumps.index);
+
+        // Give the Share Mint a name in wallets
+        let index_bump_slice: &[u8] = &[index.bump];
+        let signer_seeds = &[&[b"index".as_ref(), index.title.as_bytes(), index_bump_slice][..]];
+        create_metadata_accounts_v3(
+            CpiContext::new_with_signer(
+                self.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: self.share_metadata.to_account_info(),
+                    mint: self.share_mint.to_account_info(),
+                    mint_authority: index.to_account_info(),
+                    payer: self.payer.to_account_info(),
+                    update_authority: index.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    rent: self.rent.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            DataV2 {
+                name: index.title.clone(),
+                symbol,
+                uri,
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
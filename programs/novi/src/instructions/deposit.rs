@@ -1,11 +1,12 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{
-    token::{Mint, Token, TokenAccount, Transfer},
+    token::{transfer, Mint, SyncNative, Token, TokenAccount, Transfer},
     associated_token::AssociatedToken,
 };
 use crate::{
-    state::{DepositAccount, IndexAccount},
-    constants::{MIN_SOL_THRESHOLD, MIN_USD_THRESHOLD, MAX_SOL_THRESHOLD, MAX_USD_THRESHOLD, usdc, usdt, wsol},
+    amounts::UiAmount,
+    constants::{usdc, wsol},
+    state::{Config, DepositAccount, IndexAccount},
     errors::NoviError,
 };
 
@@ -17,12 +18,17 @@ pub struct Deposit<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
     #[account(
         init,
         payer = payer,
         seeds = [b"deposit", seed.to_le_bytes().as_ref(), user.key().as_ref()],
         bump,    
-        space = DepositAccount::INIT_SPACE + index.mint_list.len() * 1,
+        space = DepositAccount::INIT_SPACE + index.mint_list.len() * (1 + 1 + 8),
     )]
     pub deposit: Account<'info, DepositAccount>,
     #[account(
@@ -40,12 +46,31 @@ pub struct Deposit<'info> {
         associated_token::authority = deposit,
     )]
     pub deposit_token: Account<'info, TokenAccount>,
+    // Native SOL deposits leave this out and get wrapped straight into the vault
     #[account(
         mut,
         associated_token::mint = mint,
         associated_token::authority = user,
     )]
-    pub user_token: Account<'info, TokenAccount>,
+    pub user_token: Option<Account<'info, TokenAccount>>,
+    // Only for USDC bounties, the payer funds the escrow keepers get paid from
+    #[account(address = usdc::id())]
+    pub usdc: Option<Account<'info, Mint>>,
+    #[account(
+        mut,
+        token::mint = usdc,
+        token::authority = payer,
+    )]
+    pub payer_usdc: Option<Account<'info, TokenAccount>>,
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"bounty", deposit.key().as_ref()],
+        bump,
+        token::mint = usdc,
+        token::authority = deposit,
+    )]
+    pub bounty_token: Option<Account<'info, TokenAccount>>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Program<'info, Token>,
@@ -53,39 +78,80 @@ pub struct Deposit<'info> {
 }
 
 impl<'info> Deposit<'info> {        
-    pub fn deposit(&mut self, seed: u64, amount: u64, bumps: DepositBumps) -> Result<()> {
+    pub fn deposit(&mut self, seed: u64, amount: u64, max_slippage_bps: Option<u16>, bumps: DepositBumps) -> Result<()> {
+        self.config.pause_state.max(self.index.pause_state).check_inflows()?;
 
-        // We check that the Mint is correct and that the Amoun is within the threshold
-        if self.mint.key() == wsol::id() {
-            require!(amount >= MIN_SOL_THRESHOLD, NoviError::MinThreshold);
-            require!(amount <= MAX_SOL_THRESHOLD, NoviError::MaxThreshold);
-        } else if self.mint.key() == usdc::id() {
-            require!(amount >= MIN_USD_THRESHOLD, NoviError::MinThreshold);
-            require!(amount <= MAX_USD_THRESHOLD, NoviError::MaxThreshold);
-        } else if self.mint.key() == usdt::id() {
-            require!(amount >= MIN_USD_THRESHOLD, NoviError::MinThreshold);
-            require!(amount <= MAX_USD_THRESHOLD, NoviError::MaxThreshold); 
-        } else {
-            return Err(NoviError::InvalidMint.into());
-        }
+        // We check that the Mint is allowed and that the Amount is within its threshold
+        let amount = UiAmount(amount);
+        self.config.check_threshold(self.mint.key(), amount)?;
 
-        let mut mint_list: Vec<bool> = Vec::new();
-        for _ in 0..self.index.mint_list.len() {
-            mint_list.push(false);
-        }
+        // From here on everything is counted in base units of the Mint
+        let amount = amount.to_base(self.mint.decimals)?.0;
         
         // We initialize the DepositAccount and Deposit the funds
-        let mut deposit_account = self.deposit.clone();
-        deposit_account.initialize(self.user.key(), amount, mint_list, seed, bumps.deposit);
-        deposit_account.deposit(
-            amount.checked_mul(self.mint.decimals as u64).ok_or(NoviError::Overflow)?, 
-            Transfer {
-                from: self.user_token.to_account_info(),
-                to: self.deposit_token.to_account_info(),
-                authority: self.user.to_account_info(),
-            }, 
-            self.token_program.to_account_info()
-        )?;
+        let legs = self.index.mint_list.len();
+        self.deposit.initialize(self.user.key(), self.payer.key(), self.index.key(), self.mint.key(), amount, legs, max_slippage_bps, self.index.keeper_bounty, seed, bumps.deposit)?;
+
+        // Lamport bounties are reserved on the Deposit, whatever the keepers
+        // don't claim goes back to the payer with the rent when it closes
+        let reserved_lamports = self.index.keeper_bounty.reserved_lamports(legs)?;
+        if reserved_lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: self.payer.to_account_info(),
+                        to: self.deposit.to_account_info(),
+                    },
+                ),
+                reserved_lamports,
+            )?;
+        }
+
+        // USDC bounties are escrowed the same way, and only they get an escrow
+        let reserved_usdc = self.index.keeper_bounty.reserved_usdc(legs)?;
+        match (&self.payer_usdc, &self.bounty_token) {
+            (Some(payer_usdc), Some(bounty_token)) if reserved_usdc > 0 => transfer(
+                CpiContext::new(
+                    self.token_program.to_account_info(),
+                    Transfer {
+                        from: payer_usdc.to_account_info(),
+                        to: bounty_token.to_account_info(),
+                        authority: self.payer.to_account_info(),
+                    },
+                ),
+                reserved_usdc,
+            )?,
+            (_, None) if reserved_usdc == 0 => {},
+            _ => return err!(NoviError::InvalidBountyAccounts),
+        }
+
+        match &self.user_token {
+            Some(user_token) => self.deposit.deposit(
+                amount, 
+                Transfer {
+                    from: user_token.to_account_info(),
+                    to: self.deposit_token.to_account_info(),
+                    authority: self.user.to_account_info(),
+                }, 
+                self.token_program.to_account_info()
+            )?,
+            None => {
+                require_keys_eq!(self.mint.key(), wsol::id(), NoviError::MissingUserToken);
+                self.deposit.deposit_native(
+                    amount,
+                    system_program::Transfer {
+                        from: self.user.to_account_info(),
+                        to: self.deposit_token.to_account_info(),
+                    },
+                    self.system_program.to_account_info(),
+                    SyncNative {
+                        account: self.deposit_token.to_account_info(),
+                    },
+                    self.token_program.to_account_info(),
+                )?
+            }
+        }
 
         Ok(())
     }
@@ -1,16 +1,19 @@
-use anchor_lang::{
-    prelude::*, 
-    system_program::{create_account, CreateAccount},
-}; 
+use anchor_lang::prelude::*;
 use anchor_spl::{
-    associated_token::AssociatedToken, token::{Mint, Token, TokenAccount, Transfer}
+    associated_token::AssociatedToken, token::{close_account, mint_to, transfer, CloseAccount, Mint, MintTo, Token, TokenAccount, Transfer}
 };
 
 use crate::{
+    amounts::{unit, BaseAmount},
+    constants::usdc,
     errors::NoviError,
-    state::{IndexAccount, IndexProfile},
+    oracle::{leg_prices, Nav},
+    state::{Config, DepositAccount, IndexAccount, IndexProfile, KeeperBounty, SwapDispute, SwapTicket},
 };
 
+// The remaining accounts value the Index (see Nav::compute), only needed for
+// the leg that mints the first Shares of an Index with a performance fee, to
+// start its high-water mark.
 #[derive(Accounts)]
 pub struct Finalize<'info> {
     #[account(mut)]
@@ -20,6 +23,11 @@ pub struct Finalize<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
     #[account(
         mut,
         seeds = [b"index", index.title.as_bytes()],
@@ -27,13 +35,37 @@ pub struct Finalize<'info> {
     )]
     pub index: Account<'info, IndexAccount>,
     #[account(
+        init_if_needed,
+        payer = payer,
         seeds = [b"profile", index.key().as_ref(), owner.key().as_ref()],
-        bump
+        bump,
+        space = IndexProfile::space(index.mint_list.len()),
     )]
-    /// CHECK: This is fine because we're checking it in the instruction
-    pub index_profile: AccountInfo<'info>,
+    pub index_profile: Account<'info, IndexProfile>,
+    #[account(
+        mut,
+        seeds = [b"deposit", deposit.seed.to_le_bytes().as_ref(), deposit.owner.as_ref()],
+        bump = deposit.bump,
+        has_one = owner,
+        has_one = index,
+    )]
+    pub deposit: Account<'info, DepositAccount>,
+    #[account(
+        mut,
+        seeds = [b"ticket", deposit.key().as_ref(), mint.key().as_ref()],
+        bump = ticket.bump,
+        has_one = deposit,
+        has_one = owner,
+        has_one = payer,
+        has_one = mint,
+        constraint = ticket.keeper == swapper.key() @ NoviError::NotTicketKeeper,
+        close = payer,
+    )]
+    pub ticket: Account<'info, SwapTicket>,
 
     pub mint: Account<'info, Mint>,
+    #[account(address = deposit.mint)]
+    pub deposit_mint: Account<'info, Mint>,
     #[account(
         init_if_needed,
         payer = payer,
@@ -47,19 +79,106 @@ pub struct Finalize<'info> {
         associated_token::authority = swapper,
     )]
     pub swapper_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = deposit_mint,
+        associated_token::authority = swapper,
+    )]
+    pub swapper_deposit_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = deposit_mint,
+        associated_token::authority = deposit,
+    )]
+    pub deposit_token: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = index.share_mint,
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = share_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_shares: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = deposit.payer,
+    )]
+    pub deposit_payer: SystemAccount<'info>,
+    // Only for USDC bounties
+    #[account(
+        mut,
+        seeds = [b"bounty", deposit.key().as_ref()],
+        bump,
+    )]
+    pub bounty_token: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = usdc::id(),
+        token::authority = swapper,
+    )]
+    pub swapper_usdc: Option<Account<'info, TokenAccount>>,
+    /// CHECK: Oracle price of the deposit Mint, only needed when the Index has Oracles and checked against the Config
+    pub input_oracle: Option<UncheckedAccount<'info>>,
+    /// CHECK: Oracle price of the Mint, only needed when the Index has Oracles and checked against the Index
+    pub output_oracle: Option<UncheckedAccount<'info>>,
+    // Only for a leg delivered below the price band of the Oracles
+    #[account(
+        init,
+        payer = payer,
+        space = SwapDispute::INIT_SPACE,
+        seeds = [b"dispute", ticket.keeper.as_ref(), ticket.nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub dispute: Option<Account<'info, SwapDispute>>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>
 }
 
-impl<'info> Finalize<'info> {        
-    pub fn finalize(&mut self, amount: u64, bumps: FinalizeBumps) -> Result<()> {
+impl<'info> Finalize<'info> {
+    pub fn finalize(&mut self, legs: &'info [AccountInfo<'info>], bumps: FinalizeBumps) -> Result<()> {
+        // Credit what the swap actually delivered, the surplus over the quote
+        // either goes to the vault or stays with the keeper.
+        let received = self.ticket.swapped_amount(self.swapper_token.amount)?;
+        let amount = self.config.surplus_policy.credited(received, self.ticket.quoted_out_amount);
+
+        // An exact-out Swap may leave part of the leg with the swapper, which
+        // goes back to the Deposit
+        let unspent = self.ticket.unspent_amount(self.swapper_deposit_token.amount)?;
+        if unspent > 0 {
+            transfer(
+                CpiContext::new(
+                    self.token_program.to_account_info(),
+                    Transfer {
+                        from: self.swapper_deposit_token.to_account_info(),
+                        to: self.deposit_token.to_account_info(),
+                        authority: self.swapper.to_account_info(),
+                    },
+                ),
+                unspent,
+            )?;
+        }
+        let spent = self.ticket.in_amount.checked_sub(unspent).ok_or(NoviError::Underflow)?;
+
+        let mint_index = self.index.check_address(self.mint.key())?;
+        self.open_dispute_if_short(mint_index, spent, received, bumps.dispute)?;
+
+        // Pay the keeper: a cut of the leg stays in its account, lamports
+        // come out of what the Deposit reserved
+        let amount = amount.checked_sub(self.deposit.bounty.cut(amount)?).ok_or(NoviError::Underflow)?;
+        if let KeeperBounty::Lamports { per_leg } = self.deposit.bounty {
+            self.deposit.sub_lamports(per_leg)?;
+            self.swapper.add_lamports(per_leg)?;
+        }
 
         // Deposit Swapped Funds to the Index Vault
-        let index = self.index.clone();
-        index.deposit(
-            amount, 
+        self.index.deposit(
+            amount,
             Transfer {
                 from: self.swapper_token.to_account_info(),
                 to: self.index_token.to_account_info(),
@@ -68,41 +187,139 @@ impl<'info> Finalize<'info> {
             self.token_program.to_account_info(),
         )?;
 
-        // Try and deserialize the Profile, if it fails, initialize it.
-        let index_profile = self.index_profile.clone();
-        let info = index_profile.to_account_info();
-        let data = info.try_borrow_mut_data()?;
-        match IndexProfile::try_deserialize(&mut &data[..]) {
-            Ok(_) => {
-                let mut profile = IndexProfile::try_from_slice(&index_profile.data.borrow()).unwrap();
-                profile.mint_amount[index.check_address(self.mint.key())?] = profile.mint_amount[index.check_address(self.mint.key())?].checked_add(amount).ok_or(NoviError::Overflow)?;
-            },
-            Err(_) => {
-                create_account(
-                    CpiContext::new(
-                        self.system_program.to_account_info(), 
-                        CreateAccount {
-                            from: self.payer.to_account_info(),
-                            to: index_profile.to_account_info(),                    
-                        }), 
-                    Rent::get()?.minimum_balance(IndexProfile::INIT_SPACE), 
-                    IndexProfile::INIT_SPACE as u64, 
-                    &crate::ID,
-                )?;
-                
-                let mut profile = IndexProfile::try_from_slice(&index_profile.data.borrow()).unwrap();
-                profile.owner = self.owner.key();
-                let mut mint_amount: Vec<u64> = Vec::new();
-                for _ in 0..self.index.mint_list.len() {
-                    mint_amount.push(0);
-                };
-                mint_amount[index.check_address(self.mint.key())?] = amount;
-                profile.mint_amount = mint_amount;
-                profile.bump = bumps.index_profile;
-                profile.serialize(&mut *index_profile.data.borrow_mut())?;
+        // Bring the Profile up to date with the Index before crediting it
+        let mints = self.index.mint_list.len();
+        self.index_profile.initialize_if_needed(self.owner.key(), mints, bumps.index_profile);
+        IndexProfile::realloc(&mut self.index_profile, mints, self.payer.to_account_info(), self.system_program.to_account_info())?;
+
+        // Credit the Profile with the swapped leg
+        self.index_profile.credit_leg(mint_index, amount)?;
+        self.deposit.finalize_leg(mint_index, amount)?;
+
+        if let KeeperBounty::Usdc { per_leg } = self.deposit.bounty {
+            self.pay_usdc_bounty(per_leg)?;
+        }
+
+        // Once every leg of the deposit landed, wrap them into Index Shares
+        if self.deposit.is_complete() {
+            // The first deposit sets the share price at one share per whole deposited token
+            let initial_shares = BaseAmount(self.deposit.amount).rescale(self.deposit_mint.decimals, self.share_mint.decimals)?;
+            self.index.accrue_management_fee(self.share_mint.supply, Clock::get()?.unix_timestamp)?;
+            let supply = self.index.share_supply(self.share_mint.supply)?;
+            let (shares, used) = self.index.wrap_legs(&self.deposit.out_amounts, supply, initial_shares.0)?;
+            self.index_profile.debit(&used)?;
+            // The first Shares start the high-water mark at what they're worth
+            if supply == 0 && self.index.fees.performance_fee_bps > 0 {
+                let nav = Nav::compute(&self.index, &self.share_mint, legs)?;
+                self.index.start_high_water_mark(nav.share_value, shares, unit(self.share_mint.decimals)?)?;
+            }
+            // The entry fee is held back for the fee recipient
+            let shares = self.index.charge_entry_fee(shares)?;
+
+            let index_bump_slice: &[u8] = &[self.index.bump];
+            let signer_seeds = &[&[b"index".as_ref(), self.index.title.as_bytes(), index_bump_slice][..]];
+            mint_to(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    MintTo {
+                        mint: self.share_mint.to_account_info(),
+                        to: self.owner_shares.to_account_info(),
+                        authority: self.index.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                shares,
+            )?;
+
+            // What exact-out Swaps left unspent waits in the vault for the
+            // Owner to refund it, otherwise the Deposit is done
+            self.deposit_token.reload()?;
+            if self.deposit_token.amount == 0 {
+                self.close_deposit()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // What the Swap delivered below the price band is the evidence a slash
+    // of the Keeper needs. Inside the band it's slippage the quote allowed
+    // for, and under min_out swapped_amount already refused it.
+    fn open_dispute_if_short(&mut self, mint_index: usize, spent: u64, received: u64, bump: u8) -> Result<()> {
+        let prices = leg_prices(&self.config, &self.index, self.deposit.mint, mint_index, self.input_oracle.as_deref(), self.output_oracle.as_deref())?;
+        let shortfall = match prices {
+            Some((in_price, out_price)) => self.index.price_guard.shortfall((spent, self.deposit_mint.decimals), &in_price, (received, self.mint.decimals), &out_price)?,
+            None => 0,
+        };
+
+        match (&mut self.dispute, shortfall) {
+            (None, 0) => Ok(()),
+            (None, _) => err!(NoviError::MissingDispute),
+            (Some(_), 0) => err!(NoviError::UnwarrantedDispute),
+            (Some(dispute), shortfall) => {
+                dispute.initialize(self.ticket.keeper, self.ticket.owner, self.payer.key(), self.ticket.deposit_mint, shortfall, bump);
+                Ok(())
             }
         }
+    }
+
+    fn close_deposit(&self) -> Result<()> {
+        let deposit_seed_bytes = self.deposit.seed.to_le_bytes();
+        let deposit_bump_slice: &[u8] = &[self.deposit.bump];
+        let signer_seeds = &[&[b"deposit".as_ref(), deposit_seed_bytes.as_ref(), self.deposit.owner.as_ref(), deposit_bump_slice][..]];
+        close_account(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: self.deposit_token.to_account_info(),
+                    destination: self.deposit_payer.to_account_info(),
+                    authority: self.deposit.to_account_info(),
+                },
+                signer_seeds
+            )
+        )?;
+        self.deposit.close(self.deposit_payer.to_account_info())
+    }
 
+    // The escrow pays every leg the same, and the last leg takes whatever is
+    // left in it so it can close
+    fn pay_usdc_bounty(&self, per_leg: u64) -> Result<()> {
+        let (Some(bounty_token), Some(swapper_usdc)) = (&self.bounty_token, &self.swapper_usdc) else {
+            return err!(NoviError::InvalidBountyAccounts);
+        };
+        let complete = self.deposit.is_complete();
+        let amount = match complete {
+            true => bounty_token.amount,
+            false => per_leg.min(bounty_token.amount),
+        };
+
+        let deposit_seed_bytes = self.deposit.seed.to_le_bytes();
+        let deposit_bump_slice: &[u8] = &[self.deposit.bump];
+        let signer_seeds = &[&[b"deposit".as_ref(), deposit_seed_bytes.as_ref(), self.deposit.owner.as_ref(), deposit_bump_slice][..]];
+        self.deposit.withdraw(
+            amount,
+            Transfer {
+                from: bounty_token.to_account_info(),
+                to: swapper_usdc.to_account_info(),
+                authority: self.deposit.to_account_info(),
+            },
+            self.token_program.to_account_info(),
+            signer_seeds,
+        )?;
+
+        if complete {
+            close_account(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    CloseAccount {
+                        account: bounty_token.to_account_info(),
+                        destination: self.deposit_payer.to_account_info(),
+                        authority: self.deposit.to_account_info(),
+                    },
+                    signer_seeds,
+                )
+            )?;
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken, token::{transfer, Mint, Token, TokenAccount, Transfer}
+};
+
+use crate::constants::usdc;
+
+#[derive(Accounts)]
+pub struct FinalizeRedeem<'info> {
+    #[account(mut)]
+    pub swapper: Signer<'info>,
+    pub owner: SystemAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(address = usdc::id())]
+    pub usdc: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = usdc,
+        associated_token::authority = swapper,
+    )]
+    pub swapper_token: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = usdc,
+        associated_token::authority = owner,
+    )]
+    pub owner_token: Account<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> FinalizeRedeem<'info> {
+    pub fn finalize_redeem(&mut self, amount: u64) -> Result<()> {
+        // Pay the Swapped USDC out to the Owner
+        transfer(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: self.swapper_token.to_account_info(),
+                    to: self.owner_token.to_account_info(),
+                    authority: self.swapper.to_account_info(),
+                },
+            ),
+            amount,
+        )
+    }
+}
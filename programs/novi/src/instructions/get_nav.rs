@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    oracle::Nav,
+    state::IndexAccount,
+};
+
+// Read-only, the Nav comes back as the return data of the transaction. The
+// remaining accounts are the legs Nav::compute values.
+#[derive(Accounts)]
+pub struct GetNav<'info> {
+    #[account(
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+    #[account(address = index.share_mint)]
+    pub share_mint: Account<'info, Mint>,
+}
+
+impl<'info> GetNav<'info> {
+    pub fn get_nav(&self, legs: &'info [AccountInfo<'info>]) -> Result<Nav> {
+        Nav::compute(&self.index, &self.share_mint, legs)
+    }
+}
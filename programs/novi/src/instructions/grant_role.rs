@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    state::{Config, Role},
+};
+
+#[derive(Accounts)]
+#[instruction(role: Role, authority: Pubkey)]
+pub struct GrantRole<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        realloc = config.space_with_role(role, authority),
+        realloc::payer = admin,
+        realloc::zero = false,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> GrantRole<'info> {
+    pub fn grant_role(&mut self, role: Role, authority: Pubkey) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.grant_role(role, authority);
+
+        Ok(())
+    }
+}
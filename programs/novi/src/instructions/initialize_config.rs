@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    program::Novi,
+    state::Config,
+};
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"config"],
+        bump,
+        space = Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ NoviError::InvalidUpgradeAuthority)]
+    pub program: Program<'info, Novi>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ NoviError::InvalidUpgradeAuthority)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> InitializeConfig<'info> {
+    pub fn initialize_config(&mut self, admin: Pubkey, bumps: InitializeConfigBumps) -> Result<()> {
+        self.config.initialize(admin, bumps.config);
+
+        Ok(())
+    }
+}
@@ -9,3 +9,63 @@ pub use swap::*;
 
 pub mod finalize;
 pub use finalize::*;
+
+pub mod redeem;
+pub use redeem::*;
+
+pub mod redeem_swap;
+pub use redeem_swap::*;
+
+pub mod finalize_redeem;
+pub use finalize_redeem::*;
+
+pub mod redeem_shares;
+pub use redeem_shares::*;
+
+pub mod initialize_config;
+pub use initialize_config::*;
+
+pub mod update_config;
+pub use update_config::*;
+
+pub mod set_deposit_mint;
+pub use set_deposit_mint::*;
+
+pub mod accept_admin;
+pub use accept_admin::*;
+
+pub mod grant_role;
+pub use grant_role::*;
+
+pub mod update_index;
+pub use update_index::*;
+
+pub mod set_pause;
+pub use set_pause::*;
+
+pub mod refund;
+pub use refund::*;
+
+pub mod set_amm_allowlist;
+pub use set_amm_allowlist::*;
+
+pub mod set_price_guard;
+pub use set_price_guard::*;
+
+pub mod get_nav;
+pub use get_nav::*;
+
+pub mod set_fees;
+pub use set_fees::*;
+
+pub mod collect_fees;
+pub use collect_fees::*;
+
+pub mod bond_keeper;
+pub use bond_keeper::*;
+
+pub mod withdraw_keeper_bond;
+pub use withdraw_keeper_bond::*;
+
+pub mod slash_keeper;
+pub use slash_keeper::*;
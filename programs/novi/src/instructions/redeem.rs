@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Mint, Token, TokenAccount, Transfer}
+};
+
+use crate::state::{Config, IndexAccount, IndexProfile};
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+    #[account(
+        mut,
+        seeds = [b"profile", index.key().as_ref(), owner.key().as_ref()],
+        bump = index_profile.bump,
+        has_one = owner,
+    )]
+    pub index_profile: Account<'info, IndexProfile>,
+
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = index,
+    )]
+    pub index_token: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token: Account<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> Redeem<'info> {
+    pub fn redeem(&mut self, amount: u64) -> Result<()> {
+        self.config.pause_state.max(self.index.pause_state).check_outflows()?;
+
+        // Debit the Profile for the Mint we're redeeming
+        let mint_index = self.index.check_address(self.mint.key())?;
+        self.index_profile.debit_leg(mint_index, amount)?;
+
+        // Withdraw the funds from the Index Vault to the Owner
+        let index_bump_slice: &[u8] = &[self.index.bump];
+        let signer_seeds = &[&[b"index".as_ref(), self.index.title.as_bytes(), index_bump_slice][..]];
+        self.index.withdraw(
+            amount,
+            Transfer {
+                from: self.index_token.to_account_info(),
+                to: self.owner_token.to_account_info(),
+                authority: self.index.to_account_info(),
+            },
+            self.token_program.to_account_info(),
+            signer_seeds,
+        )
+    }
+}
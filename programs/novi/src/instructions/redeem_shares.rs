@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{burn, Burn, Mint, Token, TokenAccount}
+};
+
+use crate::state::{Config, IndexAccount, IndexProfile};
+
+#[derive(Accounts)]
+pub struct RedeemShares<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"profile", index.key().as_ref(), owner.key().as_ref()],
+        bump,
+        space = IndexProfile::space(index.mint_list.len()),
+    )]
+    pub index_profile: Account<'info, IndexProfile>,
+
+    #[account(
+        mut,
+        address = index.share_mint,
+    )]
+    pub share_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_shares: Account<'info, TokenAccount>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> RedeemShares<'info> {
+    pub fn redeem_shares(&mut self, shares: u64, bumps: RedeemSharesBumps) -> Result<()> {
+        self.config.pause_state.max(self.index.pause_state).check_outflows()?;
+
+        // Bring the Profile up to date with the Index before crediting it
+        let mints = self.index.mint_list.len();
+        self.index_profile.initialize_if_needed(self.owner.key(), mints, bumps.index_profile);
+        IndexProfile::realloc(&mut self.index_profile, mints, self.payer.to_account_info(), self.system_program.to_account_info())?;
+
+        // Release the pro-rata holdings backing the Shares into the Profile,
+        // but for the exit fee which stays in the Index for the fee recipient
+        self.index.accrue_management_fee(self.share_mint.supply, Clock::get()?.unix_timestamp)?;
+        let supply = self.index.share_supply(self.share_mint.supply)?;
+        let redeemed = self.index.charge_exit_fee(shares)?;
+        let released = self.index.unwrap_shares(redeemed, supply)?;
+        self.index_profile.credit(&released)?;
+
+        burn(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                Burn {
+                    mint: self.share_mint.to_account_info(),
+                    from: self.owner_shares.to_account_info(),
+                    authority: self.owner.to_account_info(),
+                },
+            ),
+            shares,
+        )
+    }
+}
@@ -0,0 +1,190 @@
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::{
+    associated_token::{get_associated_token_address, AssociatedToken},
+    token::{Mint, Token, TokenAccount, Transfer}
+};
+use solana_program::{
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    sysvar::{self, instructions}
+};
+
+use crate::{
+    constants::usdc, errors::NoviError, programs::jupiter::{
+        self, SharedAccountsRoute, SwapArgs
+    }, require_instruction_eq, state::{Config, IndexAccount, IndexProfile, KeeperAccount}
+};
+
+#[derive(Accounts)]
+pub struct InitializeRedeemSwap<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub swapper: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+    #[account(
+        mut,
+        seeds = [b"keeper", swapper.key().as_ref()],
+        bump = keeper.bump,
+    )]
+    pub keeper: Account<'info, KeeperAccount>,
+    #[account(
+        mut,
+        seeds = [b"profile", index.key().as_ref(), owner.key().as_ref()],
+        bump = index_profile.bump,
+        has_one = owner,
+    )]
+    pub index_profile: Account<'info, IndexProfile>,
+
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = index,
+    )]
+    pub index_token: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = swapper,
+    )]
+    pub swapper_token: Account<'info, TokenAccount>,
+    #[account(address = usdc::id())]
+    pub usdc: Account<'info, Mint>,
+
+    #[account(address = sysvar::instructions::ID)]
+    /// CHECK: InstructionsSysvar account
+    pub instructions_sysvar_program: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> InitializeRedeemSwap<'info> {
+    pub fn initialize_redeem_swap(&mut self, amount: u64) -> Result<()> {
+        // Only registered Keepers with enough at stake can swap redemptions
+        self.keeper.check_bond(self.config.min_keeper_bond)?;
+        self.keeper.record_swap()?;
+        self.config.pause_state.max(self.index.pause_state).check_outflows()?;
+
+        // Debit the Profile for the Mint we're redeeming
+        let mint_index = self.index.check_address(self.mint.key())?;
+        self.index_profile.debit_leg(mint_index, amount)?;
+
+        // Transfer the tokens from the index vault to the swapper
+        let index_bump_slice: &[u8] = &[self.index.bump];
+        let signer_seeds = &[&[b"index".as_ref(), self.index.title.as_bytes(), index_bump_slice][..]];
+        self.index.withdraw(
+            amount,
+            Transfer {
+                from: self.index_token.to_account_info(),
+                to: self.swapper_token.to_account_info(),
+                authority: self.index.to_account_info(),
+            },
+            self.token_program.to_account_info(),
+            signer_seeds,
+        )?;
+
+        /*
+
+            Instruction Introspection
+
+            Same guarantees as InitializeSwap, in reverse: the constituent
+            we just released has to be swapped to USDC and delivered to
+            the owner within this transaction.
+
+        */
+
+        let ixs = self.instructions_sysvar_program.to_account_info();
+        let current_index: usize = instructions::load_current_index_checked(&ixs)?.into();
+
+        require_eq!(get_stack_height(), TRANSACTION_LEVEL_STACK_HEIGHT, NoviError::CpiDisabled);
+
+        /*
+
+            Match Jupiter Swap Instruction
+
+            Ensure that the next instruction after this one is a swap in the
+            Jupiter program from the constituent to USDC. Checks include:
+
+            - Program ID and IX discriminator
+            - Token account matching
+            - Mint account matching
+            - Redeem amount matching
+            - Max slippage protection
+
+        */
+
+        // The USDC account of the swapper that FinalizeRedeem pays the owner from
+        let swapper_usdc = get_associated_token_address(&self.swapper.key(), &self.usdc.key());
+
+        let quoted_out_amount: u64;
+
+        if let Ok(ix) = instructions::load_instruction_at_checked(current_index + 1, &ixs) {
+            // Check Swap Instruction
+            require_instruction_eq!(ix, jupiter::ID, SharedAccountsRoute::DISCRIMINATOR, NoviError::InvalidSwapIx);
+            let (route_plan, shared_account_route_ix) = SharedAccountsRoute::decode(&ix.data[8..])?;
+            require_gte!(self.index.max_slippage_bps, shared_account_route_ix.slippage_bps, NoviError::InvalidSlippage);
+            self.index.check_route(&route_plan)?;
+            require_eq!(shared_account_route_ix.in_amount, amount, NoviError::InvalidAmount);
+            quoted_out_amount = shared_account_route_ix.quoted_out_amount;
+
+            // Check if the "From" and "To" mint address
+            require_keys_eq!(ix.accounts.get(7).ok_or(NoviError::InvalidFromMint)?.pubkey, self.mint.key(), NoviError::InvalidFromMint);
+            require_keys_eq!(ix.accounts.get(8).ok_or(NoviError::InvalidToMint)?.pubkey, self.usdc.key(), NoviError::InvalidToMint);
+
+            // The Swap has to spend the constituent we just released and pay
+            // into the account FinalizeRedeem pays out of, or the proceeds
+            // could go anywhere.
+            require_keys_eq!(ix.accounts.get(3).ok_or(NoviError::InvalidSwapSource)?.pubkey, self.swapper_token.key(), NoviError::InvalidSwapSource);
+            require_keys_eq!(ix.accounts.get(6).ok_or(NoviError::InvalidSwapDestination)?.pubkey, swapper_usdc, NoviError::InvalidSwapDestination);
+        } else {
+            return Err(NoviError::MissingSwapIx.into());
+        }
+
+        /*
+
+            Match FinalizeRedeem Instruction
+
+            The instruction after the swap must pay the quoted USDC out to
+            the owner. Checks include:
+
+            - Program ID and IX discriminator
+            - Owner Matching
+            - Quoted_out_amount Matching
+            - Mint Matching
+            - Swapper Token Account Matching
+
+        */
+
+        if let Ok(ix) = instructions::load_instruction_at_checked(current_index + 2, &ixs) {
+            // Check FinalizeRedeem Instruction
+            require_instruction_eq!(ix, crate::ID, crate::instruction::FinalizeRedeem::DISCRIMINATOR, NoviError::InvalidFinalizeRedeemIx);
+
+            // Data Check
+            let finalize_ix = crate::instruction::FinalizeRedeem::try_from_slice(&ix.data[8..])?;
+            require_eq!(finalize_ix.amount, quoted_out_amount, NoviError::InvalidFinalizeRedeemAmount);
+
+            // Account Check
+            require_keys_eq!(ix.accounts.get(1).ok_or(NoviError::InvalidFinalizeRedeemOwner)?.pubkey, self.owner.key(), NoviError::InvalidFinalizeRedeemOwner);
+            require_keys_eq!(ix.accounts.get(3).ok_or(NoviError::InvalidFinalizeRedeemMint)?.pubkey, self.usdc.key(), NoviError::InvalidFinalizeRedeemMint);
+            require_keys_eq!(ix.accounts.get(4).ok_or(NoviError::InvalidFinalizeRedeemSwapperToken)?.pubkey, swapper_usdc, NoviError::InvalidFinalizeRedeemSwapperToken);
+        } else {
+            return Err(NoviError::MissingFinalizeRedeemIx.into());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{close_account, CloseAccount, Mint, Token, TokenAccount, Transfer}
+};
+
+use crate::{
+    constants::usdc,
+    errors::NoviError,
+    state::{Config, DepositAccount, IndexAccount, KeeperBounty, PauseState},
+};
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"deposit", deposit.seed.to_le_bytes().as_ref(), deposit.owner.as_ref()],
+        bump = deposit.bump,
+        has_one = owner,
+        has_one = payer,
+        has_one = index,
+        close = payer,
+    )]
+    pub deposit: Account<'info, DepositAccount>,
+    #[account(
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+
+    #[account(address = deposit.mint)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = deposit,
+    )]
+    pub deposit_token: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = owner,
+    )]
+    pub owner_token: Account<'info, TokenAccount>,
+    // Only for USDC bounties, what the keepers didn't claim goes back to the payer
+    #[account(
+        mut,
+        seeds = [b"bounty", deposit.key().as_ref()],
+        bump,
+    )]
+    pub bounty_token: Option<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = usdc::id(),
+        token::authority = payer,
+    )]
+    pub payer_usdc: Option<Account<'info, TokenAccount>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> Refund<'info> {
+    pub fn refund(&mut self) -> Result<()> {
+        // Refunds unlock after the configured delay, or right away if the index isn't active
+        let paused = self.config.pause_state.max(self.index.pause_state) != PauseState::Active;
+        self.deposit.check_refundable(self.config.refund_delay_slots, paused)?;
+
+        // Send whatever is left in the vault back to the Owner
+        let deposit_seed_bytes = self.deposit.seed.to_le_bytes();
+        let deposit_bump_slice: &[u8] = &[self.deposit.bump];
+        let signer_seeds = &[&[b"deposit".as_ref(), deposit_seed_bytes.as_ref(), self.deposit.owner.as_ref(), deposit_bump_slice][..]];
+        self.deposit.withdraw(
+            self.deposit_token.amount,
+            Transfer {
+                from: self.deposit_token.to_account_info(),
+                to: self.owner_token.to_account_info(),
+                authority: self.deposit.to_account_info(),
+            },
+            self.token_program.to_account_info(),
+            signer_seeds,
+        )?;
+
+        // A complete Deposit only holds what exact-out Swaps left unspent, its
+        // escrow already paid out and closed with the last leg
+        if matches!(self.deposit.bounty, KeeperBounty::Usdc { .. }) && !self.deposit.is_complete() {
+            let (Some(bounty_token), Some(payer_usdc)) = (&self.bounty_token, &self.payer_usdc) else {
+                return err!(NoviError::InvalidBountyAccounts);
+            };
+            self.deposit.withdraw(
+                bounty_token.amount,
+                Transfer {
+                    from: bounty_token.to_account_info(),
+                    to: payer_usdc.to_account_info(),
+                    authority: self.deposit.to_account_info(),
+                },
+                self.token_program.to_account_info(),
+                signer_seeds,
+            )?;
+            close_account(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    CloseAccount {
+                        account: bounty_token.to_account_info(),
+                        destination: self.payer.to_account_info(),
+                        authority: self.deposit.to_account_info(),
+                    },
+                    signer_seeds
+                )
+            )?;
+        }
+
+        // Close the deposit_token, the DepositAccount is closed by Anchor on exit
+        close_account(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                CloseAccount {
+                    account: self.deposit_token.to_account_info(),
+                    destination: self.payer.to_account_info(),
+                    authority: self.deposit.to_account_info(),
+                },
+                signer_seeds
+            )
+        )
+    }
+}
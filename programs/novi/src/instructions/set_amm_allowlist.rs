@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    state::IndexAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(allowed_amms: Vec<u8>)]
+pub struct SetAmmAllowlist<'info> {
+    #[account(mut)]
+    pub curator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+        realloc = index.space_with_amms(allowed_amms.len()),
+        realloc::payer = curator,
+        realloc::zero = false,
+    )]
+    pub index: Account<'info, IndexAccount>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> SetAmmAllowlist<'info> {
+    pub fn set_amm_allowlist(&mut self, allowed_amms: Vec<u8>, max_hops: u8) -> Result<()> {
+        require_keys_eq!(self.curator.key(), self.index.curator, NoviError::PrivilageEscalated);
+
+        self.index.set_amm_allowlist(allowed_amms, max_hops)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    state::Config,
+};
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetDepositMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        realloc = config.space_with(mint),
+        realloc::payer = admin,
+        realloc::zero = false,
+    )]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> SetDepositMint<'info> {
+    pub fn set_deposit_mint(&mut self, mint: Pubkey, min_amount: u64, max_amount: u64) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.set_deposit_mint(mint, min_amount, max_amount)?;
+
+        Ok(())
+    }
+}
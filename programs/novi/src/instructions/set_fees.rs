@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+
+use crate::{
+    amounts::unit,
+    errors::NoviError,
+    oracle::Nav,
+    state::{FeeSettings, IndexAccount},
+};
+
+// The remaining accounts value the Index (see Nav::compute), only needed while
+// a performance fee is on before or after.
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    pub curator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+    #[account(address = index.share_mint)]
+    pub share_mint: Account<'info, Mint>,
+}
+
+impl<'info> SetFees<'info> {
+    pub fn set_fees(&mut self, fees: FeeSettings, legs: &'info [AccountInfo<'info>]) -> Result<()> {
+        require_keys_eq!(self.curator.key(), self.index.curator, NoviError::PrivilageEscalated);
+
+        // Settle the fees at the old rates before switching to the new ones
+        self.index.accrue_management_fee(self.share_mint.supply, Clock::get()?.unix_timestamp)?;
+        let performance_fee_bps = self.index.fees.performance_fee_bps;
+        let supply = self.index.share_supply(self.share_mint.supply)?;
+        // Without Oracles there's no performance fee now, and set_fees refuses a new one
+        let valued = supply > 0 && !self.index.oracles.is_empty();
+        let share_value = match valued && (performance_fee_bps > 0 || fees.performance_fee_bps > 0) {
+            true => Some(Nav::compute(&self.index, &self.share_mint, legs)?.share_value),
+            false => None,
+        };
+        let unit = unit(self.share_mint.decimals)?;
+        if let Some(share_value) = share_value.filter(|_| performance_fee_bps > 0) {
+            self.index.accrue_performance_fee(share_value, self.share_mint.supply, unit)?;
+        }
+
+        self.index.set_fees(fees)?;
+
+        // A performance fee that just got turned on only charges what's gained from here
+        if let Some(share_value) = share_value.filter(|_| performance_fee_bps == 0) {
+            let supply = self.index.share_supply(self.share_mint.supply)?;
+            self.index.start_high_water_mark(share_value, supply, unit)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Config, IndexAccount, PauseState, Role};
+
+#[derive(Accounts)]
+pub struct SetGlobalPause<'info> {
+    pub pauser: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetGlobalPause<'info> {
+    pub fn set_global_pause(&mut self, pause_state: PauseState) -> Result<()> {
+        self.config.check_role(Role::Pauser, self.pauser.key())?;
+
+        self.config.pause_state = pause_state;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetIndexPause<'info> {
+    pub pauser: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+}
+
+impl<'info> SetIndexPause<'info> {
+    pub fn set_index_pause(&mut self, pause_state: PauseState) -> Result<()> {
+        self.config.check_role(Role::Pauser, self.pauser.key())?;
+
+        self.index.pause_state = pause_state;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    oracle::PriceGuard,
+    state::IndexAccount,
+};
+
+#[derive(Accounts)]
+#[instruction(oracles: Vec<Pubkey>)]
+pub struct SetPriceGuard<'info> {
+    #[account(mut)]
+    pub curator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+        realloc = index.space_with_oracles(oracles.len()),
+        realloc::payer = curator,
+        realloc::zero = false,
+    )]
+    pub index: Account<'info, IndexAccount>,
+
+    pub system_program: Program<'info, System>
+}
+
+impl<'info> SetPriceGuard<'info> {
+    pub fn set_price_guard(&mut self, oracles: Vec<Pubkey>, price_guard: PriceGuard) -> Result<()> {
+        require_keys_eq!(self.curator.key(), self.index.curator, NoviError::PrivilageEscalated);
+
+        self.index.set_price_guard(oracles, price_guard)?;
+
+        Ok(())
+    }
+}
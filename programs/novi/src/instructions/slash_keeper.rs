@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{spl_token::native_mint, Mint};
+
+use crate::{
+    constants::wsol,
+    errors::NoviError,
+    oracle::{OraclePrice, PriceGuard},
+    state::{Config, KeeperAccount, SwapDispute},
+};
+
+// Slashes a Keeper for a leg it delivered below the price band of the Oracles,
+// by at most what the shortfall is worth in lamports, and compensates the
+// owner of the Deposit. The Dispute is settled either way, a slash of zero
+// dismisses it.
+#[derive(Accounts)]
+pub struct SlashKeeper<'info> {
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+    #[account(mut)]
+    pub payer: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper.authority.as_ref()],
+        bump = keeper.bump,
+    )]
+    pub keeper: Account<'info, KeeperAccount>,
+
+    #[account(
+        mut,
+        has_one = owner,
+        has_one = payer,
+        constraint = dispute.keeper == keeper.authority @ NoviError::MissingSlashEvidence,
+        close = payer,
+    )]
+    pub dispute: Account<'info, SwapDispute>,
+
+    // Only for deposits in another Mint than wSOL, to price the shortfall in lamports
+    #[account(address = dispute.deposit_mint)]
+    pub deposit_mint: Option<Account<'info, Mint>>,
+    /// CHECK: Oracle price of the deposit Mint, checked against the Config
+    pub deposit_oracle: Option<UncheckedAccount<'info>>,
+    /// CHECK: Oracle price of wSOL, checked against the Config
+    pub wsol_oracle: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> SlashKeeper<'info> {
+    pub fn slash_keeper(&mut self, amount: u64) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+        require_gte!(self.shortfall_lamports()?, amount, NoviError::SlashExceedsShortfall);
+
+        self.keeper.slash(amount)?;
+        self.keeper.sub_lamports(amount)?;
+        self.owner.add_lamports(amount)?;
+
+        Ok(())
+    }
+
+    fn shortfall_lamports(&self) -> Result<u64> {
+        let shortfall = self.dispute.shortfall;
+        if self.dispute.deposit_mint == wsol::id() {
+            return Ok(shortfall);
+        }
+
+        let (Some(deposit_mint), Some(deposit_oracle), Some(wsol_oracle)) = (&self.deposit_mint, &self.deposit_oracle, &self.wsol_oracle) else {
+            return err!(NoviError::MissingOracle);
+        };
+        let oracle_of = |mint| self.config.deposit_mint(mint).ok().and_then(|m| m.oracle).ok_or(NoviError::MissingOracle);
+        require_keys_eq!(deposit_oracle.key(), oracle_of(deposit_mint.key())?, NoviError::MissingOracle);
+        require_keys_eq!(wsol_oracle.key(), oracle_of(wsol::id())?, NoviError::MissingOracle);
+
+        // No Index to take a guard from, the default one still keeps out stale prices
+        let now = Clock::get()?.unix_timestamp;
+        let (deposit_price, wsol_price) = (OraclePrice::load(deposit_oracle)?, OraclePrice::load(wsol_oracle)?);
+        PriceGuard::default().check_price(&deposit_price, now)?;
+        PriceGuard::default().check_price(&wsol_price, now)?;
+
+        let lamports = PriceGuard::convert(shortfall, deposit_mint.decimals, &deposit_price, native_mint::DECIMALS, &wsol_price)?;
+        Ok(u64::try_from(lamports).map_err(|_| NoviError::Overflow)?)
+    }
+}
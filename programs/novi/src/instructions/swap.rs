@@ -1,7 +1,7 @@
 use anchor_lang::{prelude::*, Discriminator}; 
 use anchor_spl::{
     associated_token::AssociatedToken, 
-    token::{close_account, CloseAccount, Mint, Token, TokenAccount, Transfer}
+    token::{Mint, Token, TokenAccount, Transfer}
 };
 use solana_program::{
     instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
@@ -9,9 +9,9 @@ use solana_program::{
 };
 
 use crate::{
-    errors::NoviError, programs::jupiter::{
-        self, SharedAccountsRoute
-    }, require_discriminator_eq, require_instruction_eq, state::{DepositAccount, IndexAccount}
+    amounts::{mul_div, BaseAmount}, constants::TOTAL_WEIGHT_BPS, errors::NoviError, oracle::leg_prices, programs::jupiter::{
+        self, JupiterSwap, TokenLedger
+    }, require_instruction_eq, state::{Config, DepositAccount, IndexAccount, KeeperAccount, SwapTicket}
 };
 
 #[derive(Accounts)]
@@ -21,10 +21,16 @@ pub struct InitializeSwap<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
     #[account(
         mut,
         seeds = [b"deposit", deposit.seed.to_le_bytes().as_ref(), deposit.owner.as_ref()],
-        bump = deposit.bump,    
+        bump = deposit.bump,
+        has_one = index,
     )]
     pub deposit: Account<'info, DepositAccount>,
     #[account(
@@ -33,22 +39,50 @@ pub struct InitializeSwap<'info> {
         bump = index.bump,
     )]
     pub index: Account<'info, IndexAccount>,
+    #[account(
+        mut,
+        seeds = [b"keeper", swapper.key().as_ref()],
+        bump = keeper.bump,
+    )]
+    pub keeper: Account<'info, KeeperAccount>,
+    #[account(
+        init,
+        payer = payer,
+        space = SwapTicket::INIT_SPACE,
+        seeds = [b"ticket", deposit.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub ticket: Account<'info, SwapTicket>,
 
-    pub usdc: Account<'info, Mint>,
+    #[account(address = deposit.mint)]
+    pub deposit_mint: Account<'info, Mint>,
     #[account(
         mut,
-        associated_token::mint = usdc,
+        associated_token::mint = deposit_mint,
         associated_token::authority = deposit,
     )]
     pub deposit_token: Account<'info, TokenAccount>,
     #[account(
         init_if_needed,
         payer = payer,
-        associated_token::mint = usdc,
+        associated_token::mint = deposit_mint,
         associated_token::authority = swapper,
     )]
     pub swapper_token: Account<'info, TokenAccount>,
     pub mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = swapper,
+    )]
+    pub swapper_mint_token: Account<'info, TokenAccount>,
+    /// CHECK: Jupiter TokenLedger, only needed by the token ledger variants and checked against the Swap Instruction
+    pub token_ledger: Option<UncheckedAccount<'info>>,
+    /// CHECK: Oracle price of the deposit Mint, only needed when the Index has Oracles and checked against the Config
+    pub input_oracle: Option<UncheckedAccount<'info>>,
+    /// CHECK: Oracle price of the Mint, only needed when the Index has Oracles and checked against the Index
+    pub output_oracle: Option<UncheckedAccount<'info>>,
 
     #[account(address = sysvar::instructions::ID)]
     /// CHECK: InstructionsSysvar account
@@ -59,19 +93,32 @@ pub struct InitializeSwap<'info> {
 }
 
 impl<'info> InitializeSwap<'info> {        
-    pub fn initialize_swap(&mut self, amount: u64) -> Result<()> {
-        let index = self.index.clone();
-        let mut deposit = self.deposit.clone();
+    pub fn initialize_swap(&mut self, amount: u64, bumps: InitializeSwapBumps) -> Result<()> {
+        // Only registered Keepers with enough at stake can swap deposits
+        self.keeper.check_bond(self.config.min_keeper_bond)?;
+        let nonce = self.keeper.record_swap()?;
+        self.config.pause_state.max(self.index.pause_state).check_inflows()?;
 
         // Check if the Mint is in the IndexAccount mint_list and log at what position is.
-        let mint_index = index.check_address(self.mint.key())?;
-        require!(deposit.mint_list[mint_index] == false, NoviError::AlreadySwapped);        
+        let mint_index = self.index.check_address(self.mint.key())?;
+
+        // Each leg releases exactly the share of the deposit set by the index weights
+        require_eq!(amount, self.index.leg_amount(BaseAmount(self.deposit.amount), mint_index)?.0, NoviError::AmountMismatch);
+
+        // Mark the leg as swapped and take it out of what's left in the deposit
+        self.deposit.release_leg(mint_index, amount)?;
+
+        // Open the ticket that the Finalize of this leg has to consume
+        self.ticket.initialize(self.deposit.key(), self.deposit.owner, self.payer.key(), self.swapper.key(), nonce, self.mint.key(), self.deposit.mint, amount, bumps.ticket);
 
         // Transfer the tokens from the deposit to the swapper
-        let index_bump_slice: &[u8] = &[index.bump];
+        let input_balance = self.swapper_token.amount;
+        let deposit_owner = self.deposit.owner;
+        let deposit_seed_bytes = self.deposit.seed.to_le_bytes();
+        let deposit_bump_slice: &[u8] = &[self.deposit.bump];
 
-        let signer_seeds = &[&[b"index".as_ref(), index.title.as_bytes(), index_bump_slice][..]];
-        deposit.withdraw(
+        let signer_seeds = &[&[b"deposit".as_ref(), deposit_seed_bytes.as_ref(), deposit_owner.as_ref(), deposit_bump_slice][..]];
+        self.deposit.withdraw(
             amount, 
             Transfer {
                 from: self.deposit_token.to_account_info(),
@@ -82,26 +129,6 @@ impl<'info> InitializeSwap<'info> {
             signer_seeds,
         )?;
 
-        // Close the deposit_token and deposit if there is no USDC in the vault
-        let deposit_seed_bytes = deposit.seed.to_le_bytes();
-        let deposit_bump_slice: &[u8] = &[deposit.bump];
-        
-        if self.deposit_token.amount == 0 {
-            let signer_seeds = &[&[b"deposit".as_ref(), deposit_seed_bytes.as_ref(), deposit.owner.as_ref(), deposit_bump_slice][..]];
-            close_account(
-                CpiContext::new_with_signer(
-                    self.token_program.to_account_info(),
-                    CloseAccount {
-                        account: self.deposit_token.to_account_info(),
-                        destination: self.payer.to_account_info(),
-                        authority: self.deposit.to_account_info(),
-                    },
-                    signer_seeds
-                )
-            )?;
-            deposit.close(self.payer.to_account_info())?;
-        }
-
         /* 
         
             Instruction Introspection
@@ -136,9 +163,10 @@ impl<'info> InitializeSwap<'info> {
             - Program ID and IX discriminator
             - Token account matching
             - Mint account matching
-            - Deposit amount matching
+            - Deposit amount matching, or an exact-out input that fits in it
             - Minimum SOL amount matching
             - Max slippage protection
+            - Oracle price band, for Indexes with Oracles
 
             By matching token accounts against our account struct which already 
             enforces mint constraints, we should be able to deduce the mint
@@ -152,16 +180,53 @@ impl<'info> InitializeSwap<'info> {
         let quoted_out_amount: u64;
         
         if let Ok(ix) = instructions::load_instruction_at_checked(current_index + 1, &ixs) {
-            // Check Swap Instruction
-            require_instruction_eq!(ix, jupiter::ID, SharedAccountsRoute::DISCRIMINATOR, NoviError::InvalidSwapIx);
-            let shared_account_route_ix = SharedAccountsRoute::try_from_slice(&ix.data[8..])?;
-            require_eq!(shared_account_route_ix.slippage_bps, 50, NoviError::InvalidSlippage);
-            require_eq!(shared_account_route_ix.in_amount, amount, NoviError::InvalidAmount);
-            quoted_out_amount = shared_account_route_ix.quoted_out_amount.checked_mul(self.mint.decimals.into()).ok_or(NoviError::Overflow)?;
-
-            // Check if the "From" and "To" mint address
-            require_keys_eq!(ix.accounts.get(7).ok_or(NoviError::InvalidFromMint)?.pubkey, self.usdc.key(), NoviError::InvalidFromMint);
-            require_keys_eq!(ix.accounts.get(8).ok_or(NoviError::InvalidToMint)?.pubkey, self.mint.key(), NoviError::InvalidToMint);
+            // Check Swap Instruction, any Jupiter v6 swap variant is accepted
+            let swap = JupiterSwap::try_from_instruction(&ix)?;
+            // Fails with the effective bound and the quoted slippage logged
+            require_gte!(self.deposit.max_slippage(self.index.max_slippage_bps), swap.slippage_bps, NoviError::InvalidSlippage);
+            self.index.check_route(&swap.route_plan)?;
+
+            let in_amount = match (swap.in_amount, swap.layout.token_ledger) {
+                (Some(in_amount), _) => in_amount,
+                (None, Some(position)) => self.ledger_in_amount(JupiterSwap::account(&ix, position, NoviError::InvalidTokenLedger)?)?,
+                (None, None) => return Err(NoviError::InvalidSwapIx.into()),
+            };
+            // An exact-out Swap delivers its out_amount for at most the quoted
+            // input plus slippage, which has to fit in the leg. Finalize
+            // returns whatever it leaves unspent to the Deposit.
+            let slippage_bps = match swap.exact_out {
+                true => {
+                    let max_in_amount = mul_div(in_amount, TOTAL_WEIGHT_BPS + swap.slippage_bps as u64, TOTAL_WEIGHT_BPS, true)?;
+                    require_gte!(amount, max_in_amount, NoviError::InvalidAmount);
+                    0
+                }
+                false => {
+                    require_eq!(in_amount, amount, NoviError::InvalidAmount);
+                    swap.slippage_bps
+                }
+            };
+            // Jupiter quotes in base units of the output Mint, which is what Finalize credits
+            quoted_out_amount = swap.out_amount;
+            self.check_price_guard(mint_index, in_amount, quoted_out_amount)?;
+
+            // Finalize credits the balance change of the swapper's output account
+            self.ticket.record_quote(input_balance, self.swapper_mint_token.amount, quoted_out_amount, slippage_bps)?;
+
+            // Check if the "From" and "To" mint address, routes without a
+            // source mint account are pinned by their source token account.
+            if let Some(position) = swap.layout.source_mint {
+                require_keys_eq!(JupiterSwap::account(&ix, position, NoviError::InvalidFromMint)?, self.deposit_mint.key(), NoviError::InvalidFromMint);
+            }
+            require_keys_eq!(JupiterSwap::account(&ix, swap.layout.destination_mint, NoviError::InvalidToMint)?, self.mint.key(), NoviError::InvalidToMint);
+
+            // The Swap has to spend the leg we just released and pay into the
+            // account Finalize measures, or the proceeds could go anywhere.
+            require_keys_eq!(JupiterSwap::account(&ix, swap.layout.source_token, NoviError::InvalidSwapSource)?, self.swapper_token.key(), NoviError::InvalidSwapSource);
+            require_keys_eq!(JupiterSwap::account(&ix, swap.layout.destination_token, NoviError::InvalidSwapDestination)?, self.swapper_mint_token.key(), NoviError::InvalidSwapDestination);
+            if let Some(position) = swap.layout.optional_destination_token {
+                let destination = JupiterSwap::account(&ix, position, NoviError::InvalidSwapDestination)?;
+                require!(destination == jupiter::ID || destination == self.swapper_mint_token.key(), NoviError::InvalidSwapDestination);
+            }
         } else {
             return Err(NoviError::MissingSwapIx.into());
         }
@@ -175,8 +240,9 @@ impl<'info> InitializeSwap<'info> {
 
             - Program ID and IX discriminator
             - Owner Matching
-            - Quoted_out_amount Matching
+            - Deposit Matching
             - Mint Matching
+            - Swapper Token Account Matching
 
         */
 
@@ -184,17 +250,38 @@ impl<'info> InitializeSwap<'info> {
             // Check Finalize Instruction
             require_instruction_eq!(ix, crate::ID, crate::instruction::Finalize::DISCRIMINATOR, NoviError::InvalidFinalizeIx);
 
-            // Data Check
-            let finalize_ix = crate::instruction::Finalize::try_from_slice(&ix.data[8..])?;
-            require_eq!(finalize_ix.amount, quoted_out_amount, NoviError::InvalidFinalizeAmount);
-
             // Account Check
             require_keys_eq!(ix.accounts.get(1).ok_or(NoviError::InvalidFinalizeOwner)?.pubkey, self.deposit.owner, NoviError::InvalidFinalizeOwner);
-            require_keys_eq!(ix.accounts.get(5).ok_or(NoviError::InvalidFinalizeMint)?.pubkey, self.mint.key(), NoviError::InvalidFinalizeMint);
+            require_keys_eq!(ix.accounts.get(6).ok_or(NoviError::InvalidFinalizeDeposit)?.pubkey, self.deposit.key(), NoviError::InvalidFinalizeDeposit);
+            require_keys_eq!(ix.accounts.get(8).ok_or(NoviError::InvalidFinalizeMint)?.pubkey, self.mint.key(), NoviError::InvalidFinalizeMint);
+            require_keys_eq!(ix.accounts.get(11).ok_or(NoviError::InvalidFinalizeSwapperToken)?.pubkey, self.swapper_mint_token.key(), NoviError::InvalidFinalizeSwapperToken);
         } else {
             return Err(NoviError::MissingFinalizeIx.into());
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    // Indexes with Oracles only take quotes close to what the Oracles say the
+    // leg is worth, so a keeper can't fill the deposit through a thin pool.
+    fn check_price_guard(&self, mint_index: usize, in_amount: u64, out_amount: u64) -> Result<()> {
+        let Some((in_price, out_price)) = leg_prices(&self.config, &self.index, self.deposit.mint, mint_index, self.input_oracle.as_deref(), self.output_oracle.as_deref())? else {
+            return Ok(());
+        };
+        self.index.price_guard.check_quote((in_amount, self.deposit_mint.decimals), &in_price, (out_amount, self.mint.decimals), &out_price)?;
+        Ok(())
+    }
+
+    // The token ledger variants swap whatever the source account gained since
+    // set_token_ledger, which has to be exactly the leg we just released.
+    fn ledger_in_amount(&mut self, ledger_key: Pubkey) -> Result<u64> {
+        let token_ledger = self.token_ledger.as_ref().ok_or(NoviError::InvalidTokenLedger)?;
+        require_keys_eq!(token_ledger.key(), ledger_key, NoviError::InvalidTokenLedger);
+
+        let ledger = TokenLedger::try_from_account(token_ledger)?;
+        require_keys_eq!(ledger.token_account, self.swapper_token.key(), NoviError::InvalidTokenLedger);
+
+        self.swapper_token.reload()?;
+        Ok(self.swapper_token.amount.checked_sub(ledger.amount).ok_or(NoviError::Underflow)?)
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    state::{Config, Role, SurplusPolicy},
+};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn propose_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.pending_admin = Some(new_admin);
+
+        Ok(())
+    }
+
+    pub fn revoke_role(&mut self, role: Role, authority: Pubkey) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.revoke_role(role, authority)?;
+
+        Ok(())
+    }
+
+    pub fn set_refund_delay(&mut self, refund_delay_slots: u64) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.refund_delay_slots = refund_delay_slots;
+
+        Ok(())
+    }
+
+    pub fn set_surplus_policy(&mut self, surplus_policy: SurplusPolicy) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.surplus_policy = surplus_policy;
+
+        Ok(())
+    }
+
+    pub fn set_deposit_mint_oracle(&mut self, mint: Pubkey, oracle: Option<Pubkey>) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.set_deposit_mint_oracle(mint, oracle)?;
+
+        Ok(())
+    }
+
+    pub fn set_min_keeper_bond(&mut self, min_keeper_bond: u64) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.min_keeper_bond = min_keeper_bond;
+
+        Ok(())
+    }
+
+    pub fn remove_deposit_mint(&mut self, mint: Pubkey) -> Result<()> {
+        require_keys_eq!(self.admin.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.config.remove_deposit_mint(mint)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::NoviError,
+    state::{Config, IndexAccount, KeeperBounty},
+};
+
+#[derive(Accounts)]
+pub struct UpdateIndex<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"index", index.title.as_bytes()],
+        bump = index.bump,
+    )]
+    pub index: Account<'info, IndexAccount>,
+}
+
+impl<'info> UpdateIndex<'info> {
+    pub fn set_curator(&mut self, curator: Pubkey) -> Result<()> {
+        require_keys_eq!(self.authority.key(), self.config.admin, NoviError::PrivilageEscalated);
+
+        self.index.curator = curator;
+
+        Ok(())
+    }
+
+    pub fn set_keeper_bounty(&mut self, keeper_bounty: KeeperBounty) -> Result<()> {
+        require_keys_eq!(self.authority.key(), self.index.curator, NoviError::PrivilageEscalated);
+
+        self.index.set_keeper_bounty(keeper_bounty)?;
+
+        Ok(())
+    }
+
+    pub fn set_max_slippage(&mut self, max_slippage_bps: u16) -> Result<()> {
+        require_keys_eq!(self.authority.key(), self.index.curator, NoviError::PrivilageEscalated);
+
+        self.index.set_max_slippage(max_slippage_bps)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Config, KeeperAccount};
+
+#[derive(Accounts)]
+pub struct WithdrawKeeperBond<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        seeds = [b"keeper", authority.key().as_ref()],
+        bump = keeper.bump,
+        has_one = authority,
+    )]
+    pub keeper: Account<'info, KeeperAccount>,
+}
+
+impl<'info> WithdrawKeeperBond<'info> {
+    pub fn withdraw_keeper_bond(&mut self, amount: u64) -> Result<()> {
+        // Deposits become refundable after the refund delay, which is also
+        // how long a misbehaving Keeper can be slashed for them
+        self.keeper.withdraw_bond(amount, self.config.refund_delay_slots)?;
+
+        self.keeper.sub_lamports(amount)?;
+        self.authority.add_lamports(amount)?;
+
+        Ok(())
+    }
+}
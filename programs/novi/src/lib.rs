@@ -4,10 +4,14 @@ pub mod instructions;
 pub mod programs;
 pub mod errors;
 pub mod macros;
+pub mod amounts;
+pub mod oracle;
 pub mod constants;
 pub mod state;
 
 use instructions::*;
+use oracle::{Nav, PriceGuard};
+use state::{FeePayout, FeeSettings, KeeperBounty, PauseState, Role, SurplusPolicy};
 
 declare_id!("FuXing9rWvKB8zPtnUCeJGMQT4CUJx6BVVwE8XnBLPtw");
 
@@ -15,7 +19,135 @@ declare_id!("FuXing9rWvKB8zPtnUCeJGMQT4CUJx6BVVwE8XnBLPtw");
 pub mod novi {
     use super::*;
 
-    pub fn finalize(ctx: Context<Finalize>, amount: u64) -> Result<()> {
-        ctx.accounts.finalize(amount, ctx.bumps)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, admin: Pubkey) -> Result<()> {
+        ctx.accounts.initialize_config(admin, ctx.bumps)
+    }
+
+    pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.propose_admin(new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        ctx.accounts.accept_admin()
+    }
+
+    pub fn grant_role(ctx: Context<GrantRole>, role: Role, authority: Pubkey) -> Result<()> {
+        ctx.accounts.grant_role(role, authority)
+    }
+
+    pub fn revoke_role(ctx: Context<UpdateConfig>, role: Role, authority: Pubkey) -> Result<()> {
+        ctx.accounts.revoke_role(role, authority)
+    }
+
+    pub fn set_deposit_mint(ctx: Context<SetDepositMint>, mint: Pubkey, min_amount: u64, max_amount: u64) -> Result<()> {
+        ctx.accounts.set_deposit_mint(mint, min_amount, max_amount)
+    }
+
+    pub fn set_refund_delay(ctx: Context<UpdateConfig>, refund_delay_slots: u64) -> Result<()> {
+        ctx.accounts.set_refund_delay(refund_delay_slots)
+    }
+
+    pub fn set_surplus_policy(ctx: Context<UpdateConfig>, surplus_policy: SurplusPolicy) -> Result<()> {
+        ctx.accounts.set_surplus_policy(surplus_policy)
+    }
+
+    pub fn set_deposit_mint_oracle(ctx: Context<UpdateConfig>, mint: Pubkey, oracle: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.set_deposit_mint_oracle(mint, oracle)
+    }
+
+    pub fn set_min_keeper_bond(ctx: Context<UpdateConfig>, min_keeper_bond: u64) -> Result<()> {
+        ctx.accounts.set_min_keeper_bond(min_keeper_bond)
+    }
+
+    pub fn remove_deposit_mint(ctx: Context<UpdateConfig>, mint: Pubkey) -> Result<()> {
+        ctx.accounts.remove_deposit_mint(mint)
+    }
+
+    pub fn bond_keeper(ctx: Context<BondKeeper>, amount: u64) -> Result<()> {
+        ctx.accounts.bond_keeper(amount, ctx.bumps)
+    }
+
+    pub fn withdraw_keeper_bond(ctx: Context<WithdrawKeeperBond>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_keeper_bond(amount)
+    }
+
+    pub fn slash_keeper(ctx: Context<SlashKeeper>, amount: u64) -> Result<()> {
+        ctx.accounts.slash_keeper(amount)
+    }
+
+    pub fn set_global_pause(ctx: Context<SetGlobalPause>, pause_state: PauseState) -> Result<()> {
+        ctx.accounts.set_global_pause(pause_state)
+    }
+
+    pub fn set_index_pause(ctx: Context<SetIndexPause>, pause_state: PauseState) -> Result<()> {
+        ctx.accounts.set_index_pause(pause_state)
+    }
+
+    pub fn create_index(ctx: Context<CreateIndex>, title: String, mint_list: Vec<Pubkey>, weights: Vec<u16>, symbol: String, uri: String) -> Result<()> {
+        ctx.accounts.create(title, mint_list, weights, symbol, uri, ctx.bumps)
+    }
+
+    pub fn set_index_curator(ctx: Context<UpdateIndex>, curator: Pubkey) -> Result<()> {
+        ctx.accounts.set_curator(curator)
+    }
+
+    pub fn set_index_max_slippage(ctx: Context<UpdateIndex>, max_slippage_bps: u16) -> Result<()> {
+        ctx.accounts.set_max_slippage(max_slippage_bps)
+    }
+
+    pub fn set_index_keeper_bounty(ctx: Context<UpdateIndex>, keeper_bounty: KeeperBounty) -> Result<()> {
+        ctx.accounts.set_keeper_bounty(keeper_bounty)
+    }
+
+    pub fn set_index_amm_allowlist(ctx: Context<SetAmmAllowlist>, allowed_amms: Vec<u8>, max_hops: u8) -> Result<()> {
+        ctx.accounts.set_amm_allowlist(allowed_amms, max_hops)
+    }
+
+    pub fn set_index_price_guard(ctx: Context<SetPriceGuard>, oracles: Vec<Pubkey>, price_guard: PriceGuard) -> Result<()> {
+        ctx.accounts.set_price_guard(oracles, price_guard)
+    }
+
+    pub fn set_index_fees<'info>(ctx: Context<'_, '_, 'info, 'info, SetFees<'info>>, fees: FeeSettings) -> Result<()> {
+        ctx.accounts.set_fees(fees, ctx.remaining_accounts)
+    }
+
+    pub fn collect_fees<'info>(ctx: Context<'_, '_, 'info, 'info, CollectFees<'info>>, payout: FeePayout) -> Result<()> {
+        ctx.accounts.collect_fees(payout, ctx.remaining_accounts, ctx.bumps)
+    }
+
+    pub fn get_nav<'info>(ctx: Context<'_, '_, 'info, 'info, GetNav<'info>>) -> Result<Nav> {
+        ctx.accounts.get_nav(ctx.remaining_accounts)
+    }
+
+    pub fn deposit(ctx: Context<Deposit>, seed: u64, amount: u64, max_slippage_bps: Option<u16>) -> Result<()> {
+        ctx.accounts.deposit(seed, amount, max_slippage_bps, ctx.bumps)
+    }
+
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        ctx.accounts.refund()
+    }
+
+    pub fn initialize_swap(ctx: Context<InitializeSwap>, amount: u64) -> Result<()> {
+        ctx.accounts.initialize_swap(amount, ctx.bumps)
+    }
+
+    pub fn finalize<'info>(ctx: Context<'_, '_, 'info, 'info, Finalize<'info>>) -> Result<()> {
+        ctx.accounts.finalize(ctx.remaining_accounts, ctx.bumps)
+    }
+
+    pub fn redeem(ctx: Context<Redeem>, amount: u64) -> Result<()> {
+        ctx.accounts.redeem(amount)
+    }
+
+    pub fn redeem_shares(ctx: Context<RedeemShares>, shares: u64) -> Result<()> {
+        ctx.accounts.redeem_shares(shares, ctx.bumps)
+    }
+
+    pub fn initialize_redeem_swap(ctx: Context<InitializeRedeemSwap>, amount: u64) -> Result<()> {
+        ctx.accounts.initialize_redeem_swap(amount)
+    }
+
+    pub fn finalize_redeem(ctx: Context<FinalizeRedeem>, amount: u64) -> Result<()> {
+        ctx.accounts.finalize_redeem(amount)
     }
 }
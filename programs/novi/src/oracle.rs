@@ -0,0 +1,353 @@
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{Mint, TokenAccount}
+};
+use std::result::Result as StdResult;
+
+use crate::{
+    amounts::{mul_div, unit},
+    constants::{DEFAULT_MAX_CONFIDENCE_BPS, DEFAULT_MAX_PRICE_AGE_SECS, DEFAULT_PRICE_BAND_BPS, NAV_DECIMALS, TOTAL_WEIGHT_BPS},
+    errors::NoviError,
+    programs::pyth::{self, PriceUpdateV2, VerificationLevel},
+    state::{Config, IndexAccount},
+};
+
+// A price of `price * 10^expo` in the feed's quote currency per whole token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: u64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+// An oracle whose price accounts we can read. OraclePrice::load asks every
+// adapter in turn, so another oracle plugs in by implementing this and being
+// added there.
+pub trait PriceOracle {
+    // The program that owns the price accounts
+    const OWNER: Pubkey;
+
+    fn read_price(account: &AccountInfo) -> Result<OraclePrice>;
+
+    // None when the account belongs to another oracle
+    fn try_read_price(account: &AccountInfo) -> Option<Result<OraclePrice>> {
+        (*account.owner == Self::OWNER).then(|| Self::read_price(account))
+    }
+}
+
+impl PriceOracle for PriceUpdateV2 {
+    const OWNER: Pubkey = pyth::ID;
+
+    fn read_price(account: &AccountInfo) -> Result<OraclePrice> {
+        let data = account.try_borrow_data()?;
+        require!(data.len() >= 8 && data[..8] == PriceUpdateV2::DISCRIMINATOR, NoviError::InvalidOracle);
+        let update = PriceUpdateV2::deserialize(&mut &data[8..])?;
+
+        // Partially verified updates carry fewer guardian signatures than a quorum
+        require!(update.verification_level == VerificationLevel::Full, NoviError::InvalidOracle);
+        require_gt!(update.price_message.price, 0, NoviError::InvalidOracle);
+
+        Ok(OraclePrice {
+            price: update.price_message.price as u64,
+            conf: update.price_message.conf,
+            expo: update.price_message.exponent,
+            publish_time: update.price_message.publish_time,
+        })
+    }
+}
+
+impl OraclePrice {
+    pub fn load(account: &AccountInfo) -> Result<OraclePrice> {
+        PriceUpdateV2::try_read_price(account).unwrap_or(Err(NoviError::InvalidOracle.into()))
+    }
+
+    // What `amount` base units are worth in the quote currency, rounded down
+    // to `value_decimals` decimals
+    pub fn value(&self, amount: u64, decimals: u8, value_decimals: u8) -> StdResult<u64, NoviError> {
+        let quote = OraclePrice { price: 1, conf: 0, expo: 0, publish_time: self.publish_time };
+        let value = PriceGuard::convert(amount, decimals, self, value_decimals, &quote)?;
+        u64::try_from(value).map_err(|_| NoviError::Overflow)
+    }
+}
+
+// How far a Swap quote may stray from the Oracle prices, and which prices are
+// still good enough to judge it by. Set by the Curator of each Index.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PriceGuard {
+    pub band_bps: u16,
+    pub max_age_secs: u32,
+    pub max_confidence_bps: u16,
+}
+
+impl Space for PriceGuard {
+    const INIT_SPACE: usize = 2 + 4 + 2;
+}
+
+// A zeroed guard would reject every quote and every price, so an Index that
+// gets Oracles before its Curator tunes the guard starts from these
+impl Default for PriceGuard {
+    fn default() -> Self {
+        PriceGuard {
+            band_bps: DEFAULT_PRICE_BAND_BPS,
+            max_age_secs: DEFAULT_MAX_PRICE_AGE_SECS,
+            max_confidence_bps: DEFAULT_MAX_CONFIDENCE_BPS,
+        }
+    }
+}
+
+impl PriceGuard {
+    pub fn check_settings(&self) -> StdResult<(), NoviError> {
+        let bps = [self.band_bps, self.max_confidence_bps];
+        if self.max_age_secs == 0 || bps.iter().any(|&bps| bps == 0 || bps as u64 > TOTAL_WEIGHT_BPS) {
+            return Err(NoviError::InvalidPriceGuard);
+        }
+        Ok(())
+    }
+
+    pub fn check_price(&self, price: &OraclePrice, now: i64) -> StdResult<(), NoviError> {
+        if now.saturating_sub(price.publish_time) > self.max_age_secs as i64 {
+            return Err(NoviError::StalePrice);
+        }
+        if price.conf as u128 * TOTAL_WEIGHT_BPS as u128 > price.price as u128 * self.max_confidence_bps as u128 {
+            return Err(NoviError::LowConfidencePrice);
+        }
+        Ok(())
+    }
+
+    // Both amounts are in base units of their Mint, the prices in the same
+    // quote currency. The quote has to land within the band of what the input
+    // is worth in the output Mint at Oracle prices.
+    pub fn check_quote(&self, (in_amount, in_decimals): (u64, u8), in_price: &OraclePrice, (out_amount, out_decimals): (u64, u8), out_price: &OraclePrice) -> StdResult<(), NoviError> {
+        let expected = PriceGuard::convert(in_amount, in_decimals, in_price, out_decimals, out_price)?;
+        let deviation = (out_amount as u128).abs_diff(expected);
+
+        if deviation * TOTAL_WEIGHT_BPS as u128 > expected * self.band_bps as u128 {
+            return Err(NoviError::QuoteOutsidePriceBand);
+        }
+        Ok(())
+    }
+
+    // How far `received` fell below the band of what the input is worth in the
+    // output Mint, moved back into base units of the input Mint. Anything
+    // inside the band is slippage the quote allowed for.
+    pub fn shortfall(&self, (in_amount, in_decimals): (u64, u8), in_price: &OraclePrice, (received, out_decimals): (u64, u8), out_price: &OraclePrice) -> StdResult<u64, NoviError> {
+        let expected = PriceGuard::convert(in_amount, in_decimals, in_price, out_decimals, out_price)?;
+        let floor = expected - expected * self.band_bps as u128 / TOTAL_WEIGHT_BPS as u128;
+        let missing = u64::try_from(floor.saturating_sub(received as u128)).map_err(|_| NoviError::Overflow)?;
+
+        let shortfall = PriceGuard::convert(missing, out_decimals, out_price, in_decimals, in_price)?;
+        u64::try_from(shortfall).map_err(|_| NoviError::Overflow)
+    }
+
+    // in_amount * in_price / out_price, moved from the input to the output decimals
+    pub fn convert(in_amount: u64, in_decimals: u8, in_price: &OraclePrice, out_decimals: u8, out_price: &OraclePrice) -> StdResult<u128, NoviError> {
+        let mut numerator = (in_amount as u128).checked_mul(in_price.price as u128).ok_or(NoviError::Overflow)?;
+        let mut denominator = out_price.price as u128;
+
+        let exponent = in_price.expo as i64 - out_price.expo as i64 + out_decimals as i64 - in_decimals as i64;
+        let scale = u32::try_from(exponent.unsigned_abs()).ok().and_then(|e| 10u128.checked_pow(e)).ok_or(NoviError::Overflow)?;
+        match exponent >= 0 {
+            true => numerator = numerator.checked_mul(scale).ok_or(NoviError::Overflow)?,
+            false => denominator = denominator.checked_mul(scale).ok_or(NoviError::Overflow)?,
+        }
+
+        numerator.checked_div(denominator).ok_or(NoviError::Overflow)
+    }
+}
+
+// The prices a Swap of the deposit into the Mint at `mint_index` is judged
+// by, none for an Index without Oracles. The input Oracle is the one the
+// Config sets for the deposit Mint, the output Oracle the Index's own.
+pub fn leg_prices(config: &Config, index: &IndexAccount, deposit_mint: Pubkey, mint_index: usize, input_oracle: Option<&AccountInfo>, output_oracle: Option<&AccountInfo>) -> Result<Option<(OraclePrice, OraclePrice)>> {
+    let Some(&output_oracle_key) = index.oracles.get(mint_index) else {
+        return Ok(None);
+    };
+    let input_oracle_key = config.deposit_mint(deposit_mint).ok().and_then(|m| m.oracle).ok_or(NoviError::MissingOracle)?;
+
+    let input_oracle = input_oracle.ok_or(NoviError::MissingOracle)?;
+    let output_oracle = output_oracle.ok_or(NoviError::MissingOracle)?;
+    require_keys_eq!(input_oracle.key(), input_oracle_key, NoviError::MissingOracle);
+    require_keys_eq!(output_oracle.key(), output_oracle_key, NoviError::MissingOracle);
+
+    let in_price = OraclePrice::load(input_oracle)?;
+    let out_price = OraclePrice::load(output_oracle)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    index.price_guard.check_price(&in_price, now)?;
+    index.price_guard.check_price(&out_price, now)?;
+    Ok(Some((in_price, out_price)))
+}
+
+// What an Index is worth in the quote currency of its Oracles, with
+// NAV_DECIMALS decimals
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Nav {
+    // Everything in the vaults, including what Profiles hold in kind
+    pub total_value: u64,
+    // The part of the vaults backing the Shares
+    pub share_value: u64,
+    // One whole Share, zero while there are none
+    pub value_per_share: u64,
+}
+
+impl Nav {
+    // `legs` are the remaining accounts of every instruction that values the
+    // Index: the Mint, its Vault (the Index's associated token account) and
+    // its Oracle, three per Mint in the order of mint_list
+    pub fn compute<'info>(index: &Account<'info, IndexAccount>, share_mint: &Account<'info, Mint>, legs: &'info [AccountInfo<'info>]) -> Result<Nav> {
+        require!(!index.oracles.is_empty(), NoviError::NoOracles);
+        require_eq!(legs.len(), index.mint_list.len() * 3, NoviError::InvalidNavAccounts);
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut total_value: u64 = 0;
+        let mut share_value: u64 = 0;
+
+        for (i, leg) in legs.chunks_exact(3).enumerate() {
+            let (mint, vault, oracle) = (&leg[0], &leg[1], &leg[2]);
+            require_keys_eq!(mint.key(), index.mint_list[i], NoviError::InvalidNavAccounts);
+            require_keys_eq!(vault.key(), get_associated_token_address(&index.key(), mint.key), NoviError::InvalidNavAccounts);
+            require_keys_eq!(oracle.key(), index.oracles[i], NoviError::InvalidNavAccounts);
+
+            let decimals = Account::<Mint>::try_from(mint)?.decimals;
+            // The vault only exists once a Swap into this Mint got finalized
+            let balance = match vault.data_is_empty() {
+                true => 0,
+                false => Account::<TokenAccount>::try_from(vault)?.amount,
+            };
+
+            let price = OraclePrice::load(oracle)?;
+            index.price_guard.check_price(&price, now)?;
+
+            total_value = total_value.checked_add(price.value(balance, decimals, NAV_DECIMALS)?).ok_or(NoviError::Overflow)?;
+            share_value = share_value.checked_add(price.value(index.backing[i], decimals, NAV_DECIMALS)?).ok_or(NoviError::Overflow)?;
+        }
+
+        let value_per_share = match index.share_supply(share_mint.supply)? {
+            0 => 0,
+            supply => mul_div(share_value, unit(share_mint.decimals)?, supply, false)?,
+        };
+
+        Ok(Nav { total_value, share_value, value_per_share })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SOL at $150.00 and USDC at $1.00, both with Pyth's usual -8 exponent
+    const SOL: OraclePrice = OraclePrice { price: 15_000_000_000, conf: 7_500_000, expo: -8, publish_time: 1_000 };
+    const USDC: OraclePrice = OraclePrice { price: 100_000_000, conf: 50_000, expo: -8, publish_time: 1_000 };
+
+    const GUARD: PriceGuard = PriceGuard { band_bps: 100, max_age_secs: 60, max_confidence_bps: 50 };
+
+    // The account data of a Pyth price update for SOL
+    fn price_update(verification_level: VerificationLevel) -> Vec<u8> {
+        let update = PriceUpdateV2 {
+            write_authority: Pubkey::default(),
+            verification_level,
+            price_message: pyth::PriceFeedMessage {
+                feed_id: [0; 32],
+                price: SOL.price as i64,
+                conf: SOL.conf,
+                exponent: SOL.expo,
+                publish_time: SOL.publish_time,
+                prev_publish_time: SOL.publish_time - 1,
+                ema_price: SOL.price as i64,
+                ema_conf: SOL.conf,
+            },
+            posted_slot: 0,
+        };
+        let mut data = PriceUpdateV2::DISCRIMINATOR.to_vec();
+        update.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn load(mut data: Vec<u8>, owner: Pubkey) -> Result<OraclePrice> {
+        let (key, mut lamports) = (Pubkey::new_unique(), 0);
+        OraclePrice::load(&AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0))
+    }
+
+    #[test]
+    fn loads_prices_through_the_oracle_that_owns_the_account() {
+        assert_eq!(load(price_update(VerificationLevel::Full), pyth::ID).unwrap(), SOL);
+
+        let error = |result: Result<OraclePrice>| result.unwrap_err() == NoviError::InvalidOracle.into();
+        assert!(error(load(price_update(VerificationLevel::Full), Pubkey::new_unique())));
+        assert!(error(load(price_update(VerificationLevel::Partial { num_signatures: 5 }), pyth::ID)));
+    }
+
+    #[test]
+    fn converts_between_mints() {
+        // 1 SOL (9 decimals) is 150 USDC (6 decimals)
+        assert_eq!(PriceGuard::convert(1_000_000_000, 9, &SOL, 6, &USDC).unwrap(), 150_000_000);
+        // and the other way around
+        assert_eq!(PriceGuard::convert(150_000_000, 6, &USDC, 9, &SOL).unwrap(), 1_000_000_000);
+        // Different exponents cancel out
+        let usdc = OraclePrice { price: 1_000_000, expo: -6, ..USDC };
+        assert_eq!(PriceGuard::convert(1_000_000_000, 9, &SOL, 6, &usdc).unwrap(), 150_000_000);
+    }
+
+    #[test]
+    fn values_amounts_in_the_quote_currency() {
+        assert_eq!(SOL.value(2_500_000_000, 9, 6).unwrap(), 375_000_000);
+        assert_eq!(USDC.value(1_234_567, 6, 6).unwrap(), 1_234_567);
+        assert_eq!(USDC.value(1_234_567, 6, 2).unwrap(), 123);
+    }
+
+    #[test]
+    fn accepts_quotes_within_the_band() {
+        GUARD.check_quote((1_000_000_000, 9), &SOL, (150_000_000, 6), &USDC).unwrap();
+        GUARD.check_quote((1_000_000_000, 9), &SOL, (148_500_000, 6), &USDC).unwrap();
+        GUARD.check_quote((1_000_000_000, 9), &SOL, (151_500_000, 6), &USDC).unwrap();
+    }
+
+    #[test]
+    fn rejects_quotes_outside_the_band() {
+        assert!(matches!(GUARD.check_quote((1_000_000_000, 9), &SOL, (148_499_999, 6), &USDC), Err(NoviError::QuoteOutsidePriceBand)));
+        assert!(matches!(GUARD.check_quote((1_000_000_000, 9), &SOL, (151_500_001, 6), &USDC), Err(NoviError::QuoteOutsidePriceBand)));
+    }
+
+    #[test]
+    fn measures_the_shortfall_below_the_band_in_the_input_mint() {
+        // 1 SOL should fetch 150 USDC, the band starts at 148.5
+        assert_eq!(GUARD.shortfall((1_000_000_000, 9), &SOL, (150_000_000, 6), &USDC).unwrap(), 0);
+        assert_eq!(GUARD.shortfall((1_000_000_000, 9), &SOL, (148_500_000, 6), &USDC).unwrap(), 0);
+        // 1.5 USDC under the band is 0.01 SOL
+        assert_eq!(GUARD.shortfall((1_000_000_000, 9), &SOL, (147_000_000, 6), &USDC).unwrap(), 10_000_000);
+    }
+
+    #[test]
+    fn defaults_to_a_usable_guard() {
+        PriceGuard::default().check_settings().unwrap();
+        GUARD.check_settings().unwrap();
+    }
+
+    #[test]
+    fn rejects_zero_or_out_of_range_settings() {
+        for guard in [
+            PriceGuard { band_bps: 0, ..GUARD },
+            PriceGuard { max_age_secs: 0, ..GUARD },
+            PriceGuard { max_confidence_bps: 0, ..GUARD },
+            PriceGuard { band_bps: 10_001, ..GUARD },
+            PriceGuard { max_confidence_bps: 10_001, ..GUARD },
+        ] {
+            assert!(matches!(guard.check_settings(), Err(NoviError::InvalidPriceGuard)));
+        }
+    }
+
+    #[test]
+    fn rejects_stale_prices() {
+        GUARD.check_price(&SOL, 1_060).unwrap();
+        assert!(matches!(GUARD.check_price(&SOL, 1_061), Err(NoviError::StalePrice)));
+    }
+
+    #[test]
+    fn rejects_low_confidence_prices() {
+        GUARD.check_price(&SOL, 1_000).unwrap();
+        let wide = OraclePrice { conf: 75_000_001, ..SOL };
+        assert!(matches!(GUARD.check_price(&wide, 1_000), Err(NoviError::LowConfidencePrice)));
+    }
+}
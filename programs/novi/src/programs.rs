@@ -1,18 +1,108 @@
-use anchor_lang::{prelude::*, Discriminator};
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction, Discriminator};
+
+use crate::errors::NoviError;
+
 pub mod jupiter {
     use super::*;
     declare_id!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
 
+    // Every swap instruction is an optional `id`, the route plan and then
+    // fixed-size amounts. The structs below only hold the amounts: the route
+    // plan goes through RoutePlan::decode, so a route through an AMM newer
+    // than the Swap enum doesn't fail the whole instruction.
+    pub trait SwapArgs: AnchorDeserialize + Discriminator {
+        // Bytes before the route plan
+        const PREFIX: usize;
+        // Bytes after the route plan
+        const TAIL: usize;
+
+        fn decode(data: &[u8]) -> Result<(RoutePlan, Self)> {
+            require!(data.len() >= Self::PREFIX + 4 + Self::TAIL, NoviError::InvalidSwapIx);
+            let (route_plan, tail) = data[Self::PREFIX..].split_at(data.len() - Self::PREFIX - Self::TAIL);
+            Ok((RoutePlan::decode(route_plan)?, Self::try_from_slice(tail)?))
+        }
+    }
+
     #[derive(AnchorDeserialize, AnchorSerialize)]
     pub struct SharedAccountsRoute {
-        pub id: u8,
-        pub route_plan: Vec<RoutePlanStep>,
         pub in_amount: u64,
         pub quoted_out_amount: u64,
         pub slippage_bps: u16,
         pub platform_fee_bps: u8
     }
 
+    // A route plan decoded as far as the Swap enum goes. A step's size depends
+    // on its AMM, so nothing past the first unknown AMM can be located, but
+    // the amounts after the plan are still readable from the end.
+    pub struct RoutePlan {
+        pub steps: Vec<RoutePlanStep>,
+        // Number of steps in the plan, decoded or not
+        pub len: usize,
+        // Variant index of the first AMM the Swap enum doesn't know yet
+        pub unknown_amm: Option<u8>,
+    }
+
+    impl RoutePlan {
+        pub fn decode(mut data: &[u8]) -> Result<RoutePlan> {
+            let len = u32::deserialize(&mut data)? as usize;
+            let mut steps = Vec::new();
+
+            for _ in 0..len {
+                match data.first() {
+                    Some(&amm) if amm >= Swap::VARIANTS => return Ok(RoutePlan { steps, len, unknown_amm: Some(amm) }),
+                    _ => steps.push(RoutePlanStep::deserialize(&mut data)?),
+                }
+            }
+
+            require!(data.is_empty(), NoviError::InvalidSwapIx);
+            Ok(RoutePlan { steps, len, unknown_amm: None })
+        }
+
+        // Token positions are numbered in the order they're produced, 0 being
+        // the input. Each step spends a position that already exists and
+        // either feeds an existing one or opens the next, and every position
+        // that gets spent is spent in full. Past an unknown AMM only the
+        // decoded steps can be checked, and the rest may still top up what
+        // they spend.
+        pub fn check_structure(&self) -> Result<()> {
+            require!(self.len > 0, NoviError::InvalidRoutePlan);
+
+            let mut percent: Vec<u16> = vec![0];
+            for step in &self.steps {
+                let (input, output) = (step.input_index as usize, step.output_index as usize);
+                require!(input < percent.len() && output <= percent.len() && input != output, NoviError::InvalidRoutePlan);
+                if output == percent.len() {
+                    percent.push(0);
+                }
+                percent[input] = percent[input].saturating_add(step.percent as u16);
+            }
+
+            if self.unknown_amm.is_none() {
+                let spent = self.steps.iter().map(|step| step.input_index as usize);
+                for input in spent {
+                    require_eq!(percent[input], 100, NoviError::InvalidRoutePlan);
+                }
+            }
+            Ok(())
+        }
+
+        // The most Swaps any token goes through on its way out, so a split
+        // route is as many hops as its longest leg. Every step that couldn't
+        // be decoded may add one more.
+        pub fn hops(&self) -> usize {
+            let mut depth: Vec<usize> = vec![0];
+            for step in &self.steps {
+                let (input, output) = (step.input_index as usize, step.output_index as usize);
+                if depth.len() <= input.max(output) {
+                    depth.resize(input.max(output) + 1, 0);
+                }
+                depth[output] = depth[output].max(depth[input] + 1);
+            }
+            let decoded = depth.into_iter().max().unwrap_or(0);
+            decoded + (self.len - self.steps.len())
+        }
+    }
+
     #[derive(AnchorSerialize, AnchorDeserialize)]
     pub struct RoutePlanStep {
         pub swap: Swap,
@@ -21,16 +111,214 @@ pub mod jupiter {
         pub output_index: u8
     }
 
+    impl RoutePlanStep {
+        // The Borsh variant index of the Swap, which is what allowlists store
+        pub fn amm(&self) -> Result<u8> {
+            Ok(self.swap.try_to_vec()?[0])
+        }
+    }
+
     impl Discriminator for SharedAccountsRoute {
         const DISCRIMINATOR: [u8; 8] = [0xc1, 0x20, 0x9b, 0x33, 0x41, 0xd6, 0x9c, 0x81];
     }
 
+    impl SwapArgs for SharedAccountsRoute {
+        const PREFIX: usize = 1;
+        const TAIL: usize = 8 + 8 + 2 + 1;
+    }
+
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct SharedAccountsRouteWithTokenLedger {
+        pub quoted_out_amount: u64,
+        pub slippage_bps: u16,
+        pub platform_fee_bps: u8
+    }
+
+    impl Discriminator for SharedAccountsRouteWithTokenLedger {
+        const DISCRIMINATOR: [u8; 8] = [0xe6, 0x79, 0x8f, 0x50, 0x77, 0x9f, 0x6a, 0xaa];
+    }
+
+    impl SwapArgs for SharedAccountsRouteWithTokenLedger {
+        const PREFIX: usize = 1;
+        const TAIL: usize = 8 + 2 + 1;
+    }
+
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct SharedAccountsExactOutRoute {
+        pub out_amount: u64,
+        pub quoted_in_amount: u64,
+        pub slippage_bps: u16,
+        pub platform_fee_bps: u8
+    }
+
+    impl Discriminator for SharedAccountsExactOutRoute {
+        const DISCRIMINATOR: [u8; 8] = [0xb0, 0xd1, 0x69, 0xa8, 0x9a, 0x7d, 0x45, 0x3e];
+    }
+
+    impl SwapArgs for SharedAccountsExactOutRoute {
+        const PREFIX: usize = 1;
+        const TAIL: usize = 8 + 8 + 2 + 1;
+    }
+
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct Route {
+        pub in_amount: u64,
+        pub quoted_out_amount: u64,
+        pub slippage_bps: u16,
+        pub platform_fee_bps: u8
+    }
+
+    impl Discriminator for Route {
+        const DISCRIMINATOR: [u8; 8] = [0xe5, 0x17, 0xcb, 0x97, 0x7a, 0xe3, 0xad, 0x2a];
+    }
+
+    impl SwapArgs for Route {
+        const PREFIX: usize = 0;
+        const TAIL: usize = 8 + 8 + 2 + 1;
+    }
+
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct RouteWithTokenLedger {
+        pub quoted_out_amount: u64,
+        pub slippage_bps: u16,
+        pub platform_fee_bps: u8
+    }
+
+    impl Discriminator for RouteWithTokenLedger {
+        const DISCRIMINATOR: [u8; 8] = [0x96, 0x56, 0x47, 0x74, 0xa7, 0x5d, 0x0e, 0x68];
+    }
+
+    impl SwapArgs for RouteWithTokenLedger {
+        const PREFIX: usize = 0;
+        const TAIL: usize = 8 + 2 + 1;
+    }
+
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct ExactOutRoute {
+        pub out_amount: u64,
+        pub quoted_in_amount: u64,
+        pub slippage_bps: u16,
+        pub platform_fee_bps: u8
+    }
+
+    impl Discriminator for ExactOutRoute {
+        const DISCRIMINATOR: [u8; 8] = [0xd0, 0x33, 0xef, 0x97, 0x7b, 0x2b, 0xed, 0x5c];
+    }
+
+    impl SwapArgs for ExactOutRoute {
+        const PREFIX: usize = 0;
+        const TAIL: usize = 8 + 8 + 2 + 1;
+    }
+
+    // Balance snapshot taken by set_token_ledger, the ledger variants swap
+    // whatever the source account gained since.
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct TokenLedger {
+        pub token_account: Pubkey,
+        pub amount: u64
+    }
+
+    impl Discriminator for TokenLedger {
+        const DISCRIMINATOR: [u8; 8] = [0x9c, 0xf7, 0x09, 0xbc, 0x36, 0x6c, 0x55, 0x4d];
+    }
+
+    impl TokenLedger {
+        pub fn try_from_account(account: &AccountInfo) -> Result<TokenLedger> {
+            require_keys_eq!(*account.owner, ID, NoviError::InvalidTokenLedger);
+            let data = account.try_borrow_data()?;
+            require!(data.len() >= 8 && data[..8] == TokenLedger::DISCRIMINATOR, NoviError::InvalidTokenLedger);
+            Ok(TokenLedger::deserialize(&mut &data[8..])?)
+        }
+    }
+
+    // Where the accounts we check sit in each swap instruction. The non-shared
+    // routes can redirect their output to an optional destination account,
+    // which is left unset by passing the Jupiter program id.
+    pub struct AccountLayout {
+        pub source_token: usize,
+        pub destination_token: usize,
+        pub optional_destination_token: Option<usize>,
+        pub source_mint: Option<usize>,
+        pub destination_mint: usize,
+        pub token_ledger: Option<usize>,
+    }
+
+    const ROUTE_LAYOUT: AccountLayout = AccountLayout { source_token: 2, destination_token: 3, optional_destination_token: Some(4), source_mint: None, destination_mint: 5, token_ledger: None };
+    const ROUTE_WITH_TOKEN_LEDGER_LAYOUT: AccountLayout = AccountLayout { token_ledger: Some(7), ..ROUTE_LAYOUT };
+    const EXACT_OUT_ROUTE_LAYOUT: AccountLayout = AccountLayout { source_token: 2, destination_token: 3, optional_destination_token: Some(4), source_mint: Some(5), destination_mint: 6, token_ledger: None };
+    const SHARED_ACCOUNTS_LAYOUT: AccountLayout = AccountLayout { source_token: 3, destination_token: 6, optional_destination_token: None, source_mint: Some(7), destination_mint: 8, token_ledger: None };
+    const SHARED_ACCOUNTS_WITH_TOKEN_LEDGER_LAYOUT: AccountLayout = AccountLayout { token_ledger: Some(11), ..SHARED_ACCOUNTS_LAYOUT };
+
+    // Any Jupiter v6 swap instruction, reduced to what we validate
+    pub struct JupiterSwap {
+        pub route_plan: RoutePlan,
+        // None for the token ledger variants, the amount lives in the ledger
+        pub in_amount: Option<u64>,
+        // The quote for exact-in variants, the guaranteed amount for exact-out ones
+        pub out_amount: u64,
+        pub exact_out: bool,
+        pub slippage_bps: u16,
+        pub layout: AccountLayout,
+    }
+
+    impl JupiterSwap {
+        pub fn try_from_instruction(ix: &Instruction) -> Result<JupiterSwap> {
+            require_keys_eq!(ix.program_id, ID, NoviError::InvalidSwapIx);
+            require!(ix.data.len() >= 8, NoviError::InvalidSwapIx);
+            let (discriminator, data) = ix.data.split_at(8);
+
+            let swap = match <[u8; 8]>::try_from(discriminator).unwrap() {
+                Route::DISCRIMINATOR => {
+                    let (route_plan, ix) = Route::decode(data)?;
+                    JupiterSwap::exact_in(route_plan, Some(ix.in_amount), ix.quoted_out_amount, ix.slippage_bps, ROUTE_LAYOUT)
+                },
+                RouteWithTokenLedger::DISCRIMINATOR => {
+                    let (route_plan, ix) = RouteWithTokenLedger::decode(data)?;
+                    JupiterSwap::exact_in(route_plan, None, ix.quoted_out_amount, ix.slippage_bps, ROUTE_WITH_TOKEN_LEDGER_LAYOUT)
+                },
+                ExactOutRoute::DISCRIMINATOR => {
+                    let (route_plan, ix) = ExactOutRoute::decode(data)?;
+                    JupiterSwap::exact_out(route_plan, ix.quoted_in_amount, ix.out_amount, ix.slippage_bps, EXACT_OUT_ROUTE_LAYOUT)
+                },
+                SharedAccountsRoute::DISCRIMINATOR => {
+                    let (route_plan, ix) = SharedAccountsRoute::decode(data)?;
+                    JupiterSwap::exact_in(route_plan, Some(ix.in_amount), ix.quoted_out_amount, ix.slippage_bps, SHARED_ACCOUNTS_LAYOUT)
+                },
+                SharedAccountsRouteWithTokenLedger::DISCRIMINATOR => {
+                    let (route_plan, ix) = SharedAccountsRouteWithTokenLedger::decode(data)?;
+                    JupiterSwap::exact_in(route_plan, None, ix.quoted_out_amount, ix.slippage_bps, SHARED_ACCOUNTS_WITH_TOKEN_LEDGER_LAYOUT)
+                },
+                SharedAccountsExactOutRoute::DISCRIMINATOR => {
+                    let (route_plan, ix) = SharedAccountsExactOutRoute::decode(data)?;
+                    JupiterSwap::exact_out(route_plan, ix.quoted_in_amount, ix.out_amount, ix.slippage_bps, SHARED_ACCOUNTS_LAYOUT)
+                },
+                _ => return Err(NoviError::InvalidSwapIx.into()),
+            };
+
+            Ok(swap)
+        }
+
+        fn exact_in(route_plan: RoutePlan, in_amount: Option<u64>, quoted_out_amount: u64, slippage_bps: u16, layout: AccountLayout) -> JupiterSwap {
+            JupiterSwap { route_plan, in_amount, out_amount: quoted_out_amount, exact_out: false, slippage_bps, layout }
+        }
+
+        // Exact-out swaps quote the input and may spend less of it
+        fn exact_out(route_plan: RoutePlan, quoted_in_amount: u64, out_amount: u64, slippage_bps: u16, layout: AccountLayout) -> JupiterSwap {
+            JupiterSwap { route_plan, in_amount: Some(quoted_in_amount), out_amount, exact_out: true, slippage_bps, layout }
+        }
+
+        pub fn account(ix: &Instruction, position: usize, error: NoviError) -> Result<Pubkey> {
+            Ok(ix.accounts.get(position).ok_or(error)?.pubkey)
+        }
+    }
+
     #[derive(AnchorSerialize, AnchorDeserialize)]
     pub enum Side {
         Bid,
         Ask
     }
 
+    // Mirrors Jupiter's IDL up to MeteoraDlmm, newer AMMs decode as unknown
     #[derive(AnchorSerialize, AnchorDeserialize)]
     pub enum Swap {
         Saber,
@@ -73,4 +361,240 @@ pub mod jupiter {
         PerpsRemoveLiquidity,
         MeteoraDlmm
     }
-}
\ No newline at end of file
+    impl Swap {
+        // Number of variants above, any index from here on is an unknown AMM
+        pub const VARIANTS: u8 = 39;
+    }
+}
+
+pub mod pyth {
+    use super::*;
+    declare_id!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+    #[derive(AnchorDeserialize, AnchorSerialize, PartialEq, Eq)]
+    pub enum VerificationLevel {
+        Partial { num_signatures: u8 },
+        Full
+    }
+
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct PriceFeedMessage {
+        pub feed_id: [u8; 32],
+        pub price: i64,
+        pub conf: u64,
+        pub exponent: i32,
+        pub publish_time: i64,
+        pub prev_publish_time: i64,
+        pub ema_price: i64,
+        pub ema_conf: u64
+    }
+
+    // A price posted by the Pyth Solana Receiver
+    #[derive(AnchorDeserialize, AnchorSerialize)]
+    pub struct PriceUpdateV2 {
+        pub write_authority: Pubkey,
+        pub verification_level: VerificationLevel,
+        pub price_message: PriceFeedMessage,
+        pub posted_slot: u64
+    }
+
+    impl Discriminator for PriceUpdateV2 {
+        const DISCRIMINATOR: [u8; 8] = [0x22, 0xf1, 0x23, 0x63, 0x9d, 0x7e, 0xf4, 0xcd];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::jupiter::*;
+    use super::*;
+
+    // Jupiter v6 instruction data, one hex string per file, laid out after
+    // the v6 IDL. The unknown AMM fixtures use variant indexes past MeteoraDlmm.
+    // They are hand-encoded, not mainnet captures, see fixtures/jupiter/README.md.
+    fn fixture(hex: &str) -> Instruction {
+        let hex = hex.trim();
+        let data = (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap()).collect();
+        Instruction { program_id: jupiter::ID, accounts: vec![], data }
+    }
+
+    fn decode(hex: &str) -> JupiterSwap {
+        JupiterSwap::try_from_instruction(&fixture(hex)).unwrap()
+    }
+
+    fn amms(route_plan: &RoutePlan) -> Vec<u8> {
+        route_plan.steps.iter().map(|step| step.amm().unwrap()).collect()
+    }
+
+    // A one-step Whirlpool route with the given amounts
+    fn swap_ix<T: SwapArgs + AnchorSerialize>(args: T) -> Instruction {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.resize(8 + T::PREFIX, 0);
+        let step = RoutePlanStep { swap: Swap::Whirlpool { a_to_b: true }, percent: 100, input_index: 0, output_index: 1 };
+        vec![step].serialize(&mut data).unwrap();
+        args.serialize(&mut data).unwrap();
+        Instruction { program_id: jupiter::ID, accounts: vec![], data }
+    }
+
+    // (source token, destination token, optional destination, source mint, destination mint, token ledger)
+    fn positions(layout: &AccountLayout) -> (usize, usize, Option<usize>, Option<usize>, usize, Option<usize>) {
+        (layout.source_token, layout.destination_token, layout.optional_destination_token, layout.source_mint, layout.destination_mint, layout.token_ledger)
+    }
+
+    #[test]
+    fn decodes_every_swap_variant() {
+        let swap = JupiterSwap::try_from_instruction(&swap_ix(Route { in_amount: 1_000, quoted_out_amount: 2_000, slippage_bps: 10, platform_fee_bps: 0 })).unwrap();
+        assert_eq!((swap.in_amount, swap.out_amount, swap.slippage_bps, swap.exact_out), (Some(1_000), 2_000, 10, false));
+        assert_eq!(positions(&swap.layout), (2, 3, Some(4), None, 5, None));
+
+        let swap = JupiterSwap::try_from_instruction(&swap_ix(RouteWithTokenLedger { quoted_out_amount: 2_000, slippage_bps: 20, platform_fee_bps: 0 })).unwrap();
+        assert_eq!((swap.in_amount, swap.out_amount, swap.slippage_bps, swap.exact_out), (None, 2_000, 20, false));
+        assert_eq!(positions(&swap.layout), (2, 3, Some(4), None, 5, Some(7)));
+
+        let swap = JupiterSwap::try_from_instruction(&swap_ix(ExactOutRoute { out_amount: 2_000, quoted_in_amount: 1_000, slippage_bps: 30, platform_fee_bps: 0 })).unwrap();
+        assert_eq!((swap.in_amount, swap.out_amount, swap.slippage_bps, swap.exact_out), (Some(1_000), 2_000, 30, true));
+        assert_eq!(positions(&swap.layout), (2, 3, Some(4), Some(5), 6, None));
+
+        let swap = JupiterSwap::try_from_instruction(&swap_ix(SharedAccountsRoute { in_amount: 1_000, quoted_out_amount: 2_000, slippage_bps: 40, platform_fee_bps: 0 })).unwrap();
+        assert_eq!((swap.in_amount, swap.out_amount, swap.slippage_bps, swap.exact_out), (Some(1_000), 2_000, 40, false));
+        assert_eq!(positions(&swap.layout), (3, 6, None, Some(7), 8, None));
+
+        let swap = JupiterSwap::try_from_instruction(&swap_ix(SharedAccountsRouteWithTokenLedger { quoted_out_amount: 2_000, slippage_bps: 50, platform_fee_bps: 0 })).unwrap();
+        assert_eq!((swap.in_amount, swap.out_amount, swap.slippage_bps, swap.exact_out), (None, 2_000, 50, false));
+        assert_eq!(positions(&swap.layout), (3, 6, None, Some(7), 8, Some(11)));
+
+        let swap = JupiterSwap::try_from_instruction(&swap_ix(SharedAccountsExactOutRoute { out_amount: 2_000, quoted_in_amount: 1_000, slippage_bps: 60, platform_fee_bps: 0 })).unwrap();
+        assert_eq!((swap.in_amount, swap.out_amount, swap.slippage_bps, swap.exact_out), (Some(1_000), 2_000, 60, true));
+        assert_eq!(positions(&swap.layout), (3, 6, None, Some(7), 8, None));
+    }
+
+    #[test]
+    fn variants_cover_the_swap_enum() {
+        assert_eq!(Swap::MeteoraDlmm.try_to_vec().unwrap()[0] + 1, Swap::VARIANTS);
+    }
+
+    #[test]
+    fn decodes_shared_accounts_route() {
+        let swap = decode(include_str!("../fixtures/jupiter/shared_accounts_route.hex"));
+        assert_eq!(swap.in_amount, Some(100_000_000));
+        assert_eq!(swap.out_amount, 14_852_117);
+        assert_eq!(swap.slippage_bps, 50);
+        assert_eq!(swap.layout.source_token, 3);
+        assert_eq!(swap.route_plan.len, 1);
+        assert_eq!(swap.route_plan.unknown_amm, None);
+        assert_eq!(amms(&swap.route_plan), vec![17]);
+        swap.route_plan.check_structure().unwrap();
+    }
+
+    #[test]
+    fn decodes_multi_hop_and_split_routes() {
+        let swap = decode(include_str!("../fixtures/jupiter/route_two_hops.hex"));
+        assert_eq!(swap.in_amount, Some(1_000_000_000));
+        assert_eq!(swap.out_amount, 149_310_522);
+        assert_eq!(swap.slippage_bps, 30);
+        assert_eq!(swap.layout.source_token, 2);
+        assert_eq!(amms(&swap.route_plan), vec![38, 26]);
+        assert_eq!(swap.route_plan.hops(), 2);
+        swap.route_plan.check_structure().unwrap();
+
+        let swap = decode(include_str!("../fixtures/jupiter/route_split.hex"));
+        assert_eq!(swap.in_amount, Some(5_000_000_000));
+        assert_eq!(amms(&swap.route_plan), vec![17, 7]);
+        assert_eq!(swap.route_plan.hops(), 1);
+        swap.route_plan.check_structure().unwrap();
+    }
+
+    #[test]
+    fn decodes_exact_out_route() {
+        let swap = decode(include_str!("../fixtures/jupiter/shared_accounts_exact_out_route.hex"));
+        assert!(swap.exact_out);
+        assert_eq!(swap.in_amount, Some(33_712_004));
+        assert_eq!(swap.out_amount, 5_000_000);
+        assert_eq!(swap.slippage_bps, 100);
+        assert_eq!(amms(&swap.route_plan), vec![28]);
+    }
+
+    #[test]
+    fn decodes_token_ledger_route() {
+        let swap = decode(include_str!("../fixtures/jupiter/shared_accounts_route_with_token_ledger.hex"));
+        assert_eq!(swap.in_amount, None);
+        assert_eq!(swap.out_amount, 42_000);
+        assert_eq!(swap.layout.token_ledger, Some(11));
+        assert_eq!(amms(&swap.route_plan), vec![29]);
+    }
+
+    #[test]
+    fn reads_the_amounts_past_an_unknown_amm() {
+        let swap = decode(include_str!("../fixtures/jupiter/shared_accounts_route_unknown_amm.hex"));
+        assert_eq!(swap.in_amount, Some(250_000_000));
+        assert_eq!(swap.out_amount, 37_100_000);
+        assert_eq!(swap.slippage_bps, 50);
+        assert_eq!(swap.route_plan.len, 2);
+        assert_eq!(swap.route_plan.unknown_amm, Some(61));
+        assert_eq!(amms(&swap.route_plan), vec![17]);
+        assert_eq!(swap.route_plan.hops(), 2);
+        swap.route_plan.check_structure().unwrap();
+
+        let swap = decode(include_str!("../fixtures/jupiter/route_with_token_ledger_unknown_amm.hex"));
+        assert_eq!(swap.in_amount, None);
+        assert_eq!(swap.out_amount, 1_250_000);
+        assert_eq!(swap.slippage_bps, 20);
+        assert_eq!(swap.route_plan.len, 1);
+        assert_eq!(swap.route_plan.unknown_amm, Some(45));
+        assert!(swap.route_plan.steps.is_empty());
+    }
+
+    fn step(input_index: u8, output_index: u8, percent: u8) -> RoutePlanStep {
+        RoutePlanStep { swap: Swap::Whirlpool { a_to_b: true }, percent, input_index, output_index }
+    }
+
+    #[test]
+    fn counts_hops_along_the_longest_leg() {
+        // Half goes straight out, the other half through an intermediate token
+        let steps = vec![step(0, 1, 50), step(0, 2, 50), step(2, 1, 100)];
+        let route_plan = RoutePlan { steps, len: 3, unknown_amm: None };
+        route_plan.check_structure().unwrap();
+        assert_eq!(route_plan.hops(), 2);
+
+        // Undecoded steps count as one hop each
+        let route_plan = RoutePlan { steps: vec![step(0, 1, 100)], len: 3, unknown_amm: Some(200) };
+        assert_eq!(route_plan.hops(), 3);
+    }
+
+    #[test]
+    fn checks_the_decoded_part_of_a_route() {
+        // Steps before an unknown AMM may only spend part of a position
+        let route_plan = RoutePlan { steps: vec![step(0, 1, 60)], len: 2, unknown_amm: Some(200) };
+        route_plan.check_structure().unwrap();
+
+        // but they still have to spend positions that exist
+        let route_plan = RoutePlan { steps: vec![step(1, 2, 100)], len: 2, unknown_amm: Some(200) };
+        assert!(route_plan.check_structure().is_err());
+
+        // A fully decoded plan spends every position in full
+        let route_plan = RoutePlan { steps: vec![step(0, 1, 60)], len: 1, unknown_amm: None };
+        assert!(route_plan.check_structure().is_err());
+
+        let route_plan = RoutePlan { steps: vec![], len: 0, unknown_amm: None };
+        assert!(route_plan.check_structure().is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_instructions() {
+        let mut ix = fixture(include_str!("../fixtures/jupiter/shared_accounts_route.hex"));
+
+        // Bytes left over after a fully decoded route plan
+        ix.data.insert(ix.data.len() - 19, 0);
+        assert!(JupiterSwap::try_from_instruction(&ix).is_err());
+
+        // Too short to hold the amounts
+        ix.data.truncate(8 + 1 + 4 + 8);
+        assert!(JupiterSwap::try_from_instruction(&ix).is_err());
+
+        ix.data[..8].copy_from_slice(&[0; 8]);
+        assert!(JupiterSwap::try_from_instruction(&ix).is_err());
+
+        let mut ix = fixture(include_str!("../fixtures/jupiter/shared_accounts_route.hex"));
+        ix.program_id = Pubkey::default();
+        assert!(JupiterSwap::try_from_instruction(&ix).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use anchor_lang::prelude::*;
+use std::result::Result as StdResult;
+use crate::{amounts::UiAmount, constants::{DEFAULT_MIN_KEEPER_BOND, DEFAULT_REFUND_DELAY_SLOTS}, errors::NoviError};
+
+// Thresholds are in whole tokens of the Mint. The Oracle prices the Mint for
+// the Indexes that guard their Swaps with one.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DepositMint {
+    pub mint: Pubkey,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    pub oracle: Option<Pubkey>,
+}
+
+impl Space for DepositMint {
+    const INIT_SPACE: usize = 32 + 8 + 8 + 1 + 32;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Curator,
+    Pauser,
+}
+
+// Ordered from least to most restrictive, so the stricter of the global and
+// the per-index state is simply the max of the two.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PauseState {
+    Active,
+    RedeemOnly,
+    Paused,
+}
+
+impl PauseState {
+    pub fn check_inflows(self) -> StdResult<(), NoviError> {
+        match self {
+            PauseState::Active => Ok(()),
+            PauseState::RedeemOnly => Err(NoviError::RedeemOnly),
+            PauseState::Paused => Err(NoviError::Paused),
+        }
+    }
+
+    pub fn check_outflows(self) -> StdResult<(), NoviError> {
+        match self {
+            PauseState::Paused => Err(NoviError::Paused),
+            _ => Ok(()),
+        }
+    }
+}
+
+// What happens to whatever a swap delivers above its quote
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SurplusPolicy {
+    Vault,
+    Keeper,
+}
+
+impl SurplusPolicy {
+    pub fn credited(self, received: u64, quoted_out_amount: u64) -> u64 {
+        match self {
+            SurplusPolicy::Vault => received,
+            SurplusPolicy::Keeper => received.min(quoted_out_amount),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RoleGrant {
+    pub role: Role,
+    pub authority: Pubkey,
+}
+
+impl Space for RoleGrant {
+    const INIT_SPACE: usize = 1 + 32;
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub pause_state: PauseState,
+    pub refund_delay_slots: u64,
+    pub surplus_policy: SurplusPolicy,
+    // Lamports a Keeper has to have bonded to swap deposits
+    pub min_keeper_bond: u64,
+    pub roles: Vec<RoleGrant>,
+    pub deposit_mints: Vec<DepositMint>,
+    pub bump: u8,
+}
+
+impl Space for Config {
+    const INIT_SPACE: usize = 8 + 32 + 1 + 32 + 1 + 8 + 1 + 8 + 4 + 4 + 1;
+}
+
+impl Config {
+    pub fn initialize(&mut self, admin: Pubkey, bump: u8) {
+        self.admin = admin;
+        self.pending_admin = None;
+        self.pause_state = PauseState::Active;
+        self.refund_delay_slots = DEFAULT_REFUND_DELAY_SLOTS;
+        self.surplus_policy = SurplusPolicy::Vault;
+        self.min_keeper_bond = DEFAULT_MIN_KEEPER_BOND;
+        self.roles = Vec::new();
+        self.deposit_mints = Vec::new();
+        self.bump = bump;
+    }
+
+    fn space(roles: usize, deposit_mints: usize) -> usize {
+        Config::INIT_SPACE + roles * RoleGrant::INIT_SPACE + deposit_mints * DepositMint::INIT_SPACE
+    }
+
+    pub fn space_with(&self, mint: Pubkey) -> usize {
+        let deposit_mints = match self.deposit_mint(mint) {
+            Ok(_) => self.deposit_mints.len(),
+            Err(_) => self.deposit_mints.len() + 1,
+        };
+        Config::space(self.roles.len(), deposit_mints)
+    }
+
+    pub fn space_with_role(&self, role: Role, authority: Pubkey) -> usize {
+        let roles = match self.granted(role, authority) {
+            true => self.roles.len(),
+            false => self.roles.len() + 1,
+        };
+        Config::space(roles, self.deposit_mints.len())
+    }
+
+    fn granted(&self, role: Role, authority: Pubkey) -> bool {
+        self.roles.iter().any(|r| r.role == role && r.authority == authority)
+    }
+
+    // The Admin holds every Role, anyone else needs an explicit grant
+    pub fn has_role(&self, role: Role, authority: Pubkey) -> bool {
+        authority == self.admin || self.granted(role, authority)
+    }
+
+    pub fn check_role(&self, role: Role, authority: Pubkey) -> StdResult<(), NoviError> {
+        match self.has_role(role, authority) {
+            true => Ok(()),
+            false => Err(NoviError::MissingRole),
+        }
+    }
+
+    pub fn grant_role(&mut self, role: Role, authority: Pubkey) {
+        if !self.granted(role, authority) {
+            self.roles.push(RoleGrant { role, authority });
+        }
+    }
+
+    pub fn revoke_role(&mut self, role: Role, authority: Pubkey) -> StdResult<(), NoviError> {
+        match self.roles.iter().position(|r| r.role == role && r.authority == authority) {
+            Some(index) => {
+                self.roles.remove(index);
+                Ok(())
+            },
+            None => Err(NoviError::MissingRole),
+        }
+    }
+
+    pub fn deposit_mint(&self, mint: Pubkey) -> StdResult<&DepositMint, NoviError> {
+        match self.deposit_mints.iter().find(|m| m.mint == mint) {
+            Some(deposit_mint) => Ok(deposit_mint),
+            None => Err(NoviError::InvalidMint),
+        }
+    }
+
+    pub fn check_threshold(&self, mint: Pubkey, amount: UiAmount) -> StdResult<(), NoviError> {
+        let deposit_mint = self.deposit_mint(mint)?;
+        if amount < UiAmount(deposit_mint.min_amount) {
+            return Err(NoviError::MinThreshold);
+        }
+        if amount > UiAmount(deposit_mint.max_amount) {
+            return Err(NoviError::MaxThreshold);
+        }
+        Ok(())
+    }
+
+    pub fn set_deposit_mint(&mut self, mint: Pubkey, min_amount: u64, max_amount: u64) -> StdResult<(), NoviError> {
+        if min_amount > max_amount {
+            return Err(NoviError::InvalidThresholds);
+        }
+
+        match self.deposit_mints.iter_mut().find(|m| m.mint == mint) {
+            Some(deposit_mint) => {
+                deposit_mint.min_amount = min_amount;
+                deposit_mint.max_amount = max_amount;
+            },
+            None => self.deposit_mints.push(DepositMint { mint, min_amount, max_amount, oracle: None }),
+        }
+        Ok(())
+    }
+
+    pub fn set_deposit_mint_oracle(&mut self, mint: Pubkey, oracle: Option<Pubkey>) -> StdResult<(), NoviError> {
+        match self.deposit_mints.iter_mut().find(|m| m.mint == mint) {
+            Some(deposit_mint) => {
+                deposit_mint.oracle = oracle;
+                Ok(())
+            },
+            None => Err(NoviError::InvalidMint),
+        }
+    }
+
+    pub fn remove_deposit_mint(&mut self, mint: Pubkey) -> StdResult<(), NoviError> {
+        match self.deposit_mints.iter().position(|m| m.mint == mint) {
+            Some(index) => {
+                self.deposit_mints.remove(index);
+                Ok(())
+            },
+            None => Err(NoviError::InvalidMint),
+        }
+    }
+}
@@ -1,29 +1,87 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Transfer};
-use solana_program::system_program;
+use anchor_lang::system_program;
+use anchor_spl::token::{sync_native, transfer, SyncNative, Transfer};
 
-use crate::errors::NoviError;
+use crate::{constants::TOTAL_WEIGHT_BPS, errors::NoviError, state::KeeperBounty};
 
 #[account]
 pub struct DepositAccount {
     pub owner: Pubkey,
+    // Funded the rent of the Deposit and its vault, and gets it back on close
+    pub payer: Pubkey,
+    pub index: Pubkey,
+    pub mint: Pubkey,
     pub amount: u64,
+    pub remaining: u64,
     pub mint_list: Vec<bool>,
+    pub finalized: Vec<bool>,
+    pub out_amounts: Vec<u64>,
+    pub max_slippage_bps: Option<u16>,
+    // The Index bounty at the time of the deposit
+    pub bounty: KeeperBounty,
     pub seed: u64,
+    pub slot: u64,
     pub bump: u8,
 }
 
 impl Space for DepositAccount {
-    const INIT_SPACE: usize = 8 + 32 + 8 + 4 + 8 + 1;
+    const INIT_SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 8 + 4 + 4 + 4 + 3 + KeeperBounty::INIT_SPACE + 8 + 8 + 1;
 }
 
 impl DepositAccount {
-    pub fn initialize(&mut self, owner: Pubkey, amount: u64, mint: Vec<bool>, seed: u64, bump: u8) {
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(&mut self, owner: Pubkey, payer: Pubkey, index: Pubkey, mint: Pubkey, amount: u64, legs: usize, max_slippage_bps: Option<u16>, bounty: KeeperBounty, seed: u64, bump: u8) -> Result<()> {
+        if let Some(max_slippage_bps) = max_slippage_bps {
+            require_gte!(TOTAL_WEIGHT_BPS, max_slippage_bps as u64, NoviError::InvalidMaxSlippage);
+        }
+
         self.owner = owner;
+        self.payer = payer;
+        self.index = index;
+        self.mint = mint;
         self.amount = amount;
-        self.mint_list = mint;
+        self.remaining = amount;
+        self.mint_list = vec![false; legs];
+        self.finalized = vec![false; legs];
+        self.out_amounts = vec![0; legs];
+        self.max_slippage_bps = max_slippage_bps;
+        self.bounty = bounty;
         self.seed = seed;
+        self.slot = Clock::get()?.slot;
         self.bump = bump;
+        Ok(())
+    }
+
+    // The depositor can only tighten the Index bound, never loosen it
+    pub fn max_slippage(&self, index_max_slippage_bps: u16) -> u16 {
+        match self.max_slippage_bps {
+            Some(max_slippage_bps) => max_slippage_bps.min(index_max_slippage_bps),
+            None => index_max_slippage_bps,
+        }
+    }
+
+    pub fn check_refundable(&self, refund_delay_slots: u64, paused: bool) -> Result<()> {
+        let unlock_slot = self.slot.checked_add(refund_delay_slots).ok_or(NoviError::Overflow)?;
+        require!(paused || Clock::get()?.slot >= unlock_slot, NoviError::RefundLocked);
+        Ok(())
+    }
+
+    pub fn release_leg(&mut self, mint_index: usize, amount: u64) -> Result<()> {
+        require!(!self.mint_list[mint_index], NoviError::AlreadySwapped);
+        self.mint_list[mint_index] = true;
+        self.remaining = self.remaining.checked_sub(amount).ok_or(NoviError::Underflow)?;
+        Ok(())
+    }
+
+    pub fn finalize_leg(&mut self, mint_index: usize, amount: u64) -> Result<()> {
+        require!(!self.finalized[mint_index], NoviError::AlreadyFinalized);
+        self.finalized[mint_index] = true;
+        self.out_amounts[mint_index] = amount;
+        Ok(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0 && self.finalized.iter().all(|&f| f)
     }
 
     pub fn deposit<'info>(
@@ -37,23 +95,28 @@ impl DepositAccount {
         transfer(CpiContext::new(program, accounts), amount)
     }
 
+    pub fn deposit_native<'info>(
+        &self,
+        amount: u64,
+        accounts: system_program::Transfer<'info>,
+        program: AccountInfo<'info>,
+        sync_accounts: SyncNative<'info>,
+        token_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        require_eq!(amount, self.amount, NoviError::AmountMismatch);
+
+        // Move the lamports into the wSOL vault and let the Token Program pick them up
+        system_program::transfer(CpiContext::new(program, accounts), amount)?;
+        sync_native(CpiContext::new(token_program, sync_accounts))
+    }
+
     pub fn withdraw<'info>(
-        &mut self,
+        &self,
         amount: u64,
         accounts: Transfer<'info>,
         program: AccountInfo<'info>,
         signer_seeds: &[&[&[u8]]]
     ) -> Result<()> {
-        require_eq!(amount, self.amount.checked_div(self.mint_list.len() as u64).ok_or(NoviError::Overflow)?, NoviError::AmountMismatch);        
         transfer(CpiContext::new_with_signer(program, accounts, signer_seeds), amount)
     }
-
-    pub fn close<'info>(deposit: AccountInfo<'info>, payer: AccountInfo<'info>) -> Result<()> {
-        let dest_starting_lamports = payer.lamports();
-        **payer.lamports.borrow_mut() = dest_starting_lamports.checked_add(deposit.lamports()).unwrap();
-        **deposit.lamports.borrow_mut() = 0;
-    
-        deposit.assign(&system_program::ID);
-        deposit.realloc(0, false).map_err(Into::into)
-    }
 }
\ No newline at end of file
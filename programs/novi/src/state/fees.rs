@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use std::result::Result as StdResult;
+
+use crate::{
+    amounts::mul_div,
+    constants::{SECONDS_PER_YEAR, TOTAL_WEIGHT_BPS},
+    errors::NoviError,
+};
+
+// What the Curator of an Index charges, all of it taken in Shares
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct FeeSettings {
+    pub recipient: Pubkey,
+    // Per year, streamed by the second
+    pub management_fee_bps: u16,
+    // Of the NAV per Share gained above the high-water mark
+    pub performance_fee_bps: u16,
+    // Of the Shares minted by a deposit
+    pub entry_fee_bps: u16,
+    // Of the Shares redeemed
+    pub exit_fee_bps: u16,
+}
+
+impl Space for FeeSettings {
+    const INIT_SPACE: usize = 32 + 2 + 2 + 2 + 2;
+}
+
+// How collect_fees pays the recipient: the accrued Shares themselves, or the
+// holdings backing them, credited in kind to the recipient's Index Profile
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FeePayout {
+    Shares,
+    Vaults,
+}
+
+impl FeeSettings {
+    pub fn check_settings(&self) -> StdResult<(), NoviError> {
+        let fees = [self.management_fee_bps, self.performance_fee_bps, self.entry_fee_bps, self.exit_fee_bps];
+        if fees.iter().any(|&fee| fee as u64 > TOTAL_WEIGHT_BPS) {
+            return Err(NoviError::InvalidFees);
+        }
+        Ok(())
+    }
+
+    // The fee on an amount of Shares, rounded down
+    pub fn fee_shares(shares: u64, fee_bps: u16) -> StdResult<u64, NoviError> {
+        mul_div(shares, fee_bps as u64, TOTAL_WEIGHT_BPS, false)
+    }
+
+    // Shares minting the management fee for `elapsed` seconds, dilution being
+    // small enough between accruals to charge it on the current supply
+    pub fn management_fee_shares(&self, supply: u64, elapsed: u64) -> StdResult<u64, NoviError> {
+        let rate = (self.management_fee_bps as u64).checked_mul(elapsed).ok_or(NoviError::Overflow)?;
+        mul_div(supply, rate, SECONDS_PER_YEAR * TOTAL_WEIGHT_BPS, false)
+    }
+
+    // Shares worth the performance fee on the gain of `nav_per_share` over the
+    // high-water mark, so that after minting them the fee is the only dilution
+    pub fn performance_fee_shares(&self, share_value: u64, supply: u64, nav_per_share: u64, high_water_mark: u64, unit: u64) -> StdResult<u64, NoviError> {
+        if nav_per_share <= high_water_mark {
+            return Ok(0);
+        }
+        let gain = mul_div(nav_per_share - high_water_mark, supply, unit, false)?;
+        let fee_value = mul_div(gain, self.performance_fee_bps as u64, TOTAL_WEIGHT_BPS, false)?;
+        match share_value.checked_sub(fee_value) {
+            Some(0) | None => Err(NoviError::InvalidFees),
+            Some(rest) => mul_div(fee_value, supply, rest, false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEES: FeeSettings = FeeSettings {
+        recipient: Pubkey::new_from_array([0; 32]),
+        management_fee_bps: 200,
+        performance_fee_bps: 2_000,
+        entry_fee_bps: 30,
+        exit_fee_bps: 50,
+    };
+
+    #[test]
+    fn takes_bps_of_shares() {
+        assert_eq!(FeeSettings::fee_shares(1_000_000, FEES.entry_fee_bps).unwrap(), 3_000);
+        assert_eq!(FeeSettings::fee_shares(1_000_000, FEES.exit_fee_bps).unwrap(), 5_000);
+        assert_eq!(FeeSettings::fee_shares(199, FEES.exit_fee_bps).unwrap(), 0);
+    }
+
+    #[test]
+    fn streams_the_management_fee() {
+        // 2% a year of 1_000_000 Shares
+        assert_eq!(FEES.management_fee_shares(1_000_000, SECONDS_PER_YEAR).unwrap(), 20_000);
+        assert_eq!(FEES.management_fee_shares(1_000_000, SECONDS_PER_YEAR / 2).unwrap(), 10_000);
+        assert_eq!(FEES.management_fee_shares(1_000_000, 0).unwrap(), 0);
+        assert_eq!(FEES.management_fee_shares(0, SECONDS_PER_YEAR).unwrap(), 0);
+    }
+
+    #[test]
+    fn charges_performance_above_the_high_water_mark() {
+        // 1_000 Shares (6 decimals) worth $1.50 each, up from $1.00
+        let unit = 1_000_000;
+        let supply = 1_000 * unit;
+        let share_value = 1_500 * unit;
+        let fee_shares = FEES.performance_fee_shares(share_value, supply, 1_500_000, 1_000_000, unit).unwrap();
+
+        // 20% of the $500 gain is $100, which the new Shares are worth
+        let fee_value = mul_div(fee_shares, share_value, supply + fee_shares, false).unwrap();
+        assert_eq!(fee_value, 99_999_999);
+        assert_eq!(FEES.performance_fee_shares(share_value, supply, 1_500_000, 1_500_000, unit).unwrap(), 0);
+        assert_eq!(FEES.performance_fee_shares(share_value, supply, 1_400_000, 1_500_000, unit).unwrap(), 0);
+    }
+
+    #[test]
+    fn rejects_fees_above_100_percent() {
+        FEES.check_settings().unwrap();
+        let fees = FeeSettings { exit_fee_bps: 10_001, ..FEES };
+        assert!(matches!(fees.check_settings(), Err(NoviError::InvalidFees)));
+    }
+}
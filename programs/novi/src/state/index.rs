@@ -1,26 +1,292 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{transfer, Transfer};
 use std::result::Result as StdResult;
-use crate::errors::NoviError;
+use crate::{
+    amounts::{mul_div, BaseAmount},
+    constants::{DEFAULT_MAX_HOPS, DEFAULT_MAX_SLIPPAGE_BPS, TOTAL_WEIGHT_BPS},
+    errors::NoviError,
+    oracle::PriceGuard,
+    programs::jupiter::{RoutePlan, Swap},
+    state::{FeeSettings, PauseState},
+};
+
+// What a keeper earns for settling one leg of a deposit: lamports or USDC the
+// deposit reserves up front, or a cut of what the leg's Swap delivered
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeeperBounty {
+    #[default]
+    None,
+    Lamports { per_leg: u64 },
+    Bps { bps: u16 },
+    // In base units of USDC, escrowed in the deposit's bounty Token Account
+    Usdc { per_leg: u64 },
+}
+
+impl Space for KeeperBounty {
+    const INIT_SPACE: usize = 1 + 8;
+}
+
+impl KeeperBounty {
+    pub fn check_settings(self) -> StdResult<(), NoviError> {
+        match self {
+            KeeperBounty::Bps { bps } if bps as u64 > TOTAL_WEIGHT_BPS => Err(NoviError::InvalidKeeperBounty),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn reserved_lamports(self, legs: usize) -> StdResult<u64, NoviError> {
+        match self {
+            KeeperBounty::Lamports { per_leg } => per_leg.checked_mul(legs as u64).ok_or(NoviError::Overflow),
+            _ => Ok(0),
+        }
+    }
+
+    pub fn reserved_usdc(self, legs: usize) -> StdResult<u64, NoviError> {
+        match self {
+            KeeperBounty::Usdc { per_leg } => per_leg.checked_mul(legs as u64).ok_or(NoviError::Overflow),
+            _ => Ok(0),
+        }
+    }
+
+    // The part of a settled leg the keeper keeps, in the leg's Mint
+    pub fn cut(self, amount: u64) -> StdResult<u64, NoviError> {
+        match self {
+            KeeperBounty::Bps { bps } => mul_div(amount, bps as u64, TOTAL_WEIGHT_BPS, false),
+            _ => Ok(0),
+        }
+    }
+}
 
 #[account]
 pub struct IndexAccount {
     pub title: String,
+    pub curator: Pubkey,
     pub mint_list: Vec<Pubkey>,
+    pub weights: Vec<u16>,
+    pub share_mint: Pubkey,
+    pub backing: Vec<u64>,
+    pub pause_state: PauseState,
+    pub max_slippage_bps: u16,
+    pub allowed_amms: Vec<u8>,
+    pub max_hops: u8,
+    pub oracles: Vec<Pubkey>,
+    pub price_guard: PriceGuard,
+    pub fees: FeeSettings,
+    // Fee Shares owed to the recipient but not minted yet. They count towards
+    // the supply, so that the holders' claim on the backing is already diluted.
+    pub pending_fee_shares: u64,
+    pub fees_accrued_at: i64,
+    // NAV per Share the performance fee was last charged up to
+    pub high_water_mark: u64,
+    pub keeper_bounty: KeeperBounty,
     pub bump: u8,
 }
 
 impl Space for IndexAccount {
-    const INIT_SPACE: usize = 8 + 4 + 4 + 1;
+    const INIT_SPACE: usize = 8 + 4 + 32 + 4 + 4 + 32 + 4 + 1 + 2 + 4 + 1 + 4 + PriceGuard::INIT_SPACE + FeeSettings::INIT_SPACE + 8 + 8 + 8 + KeeperBounty::INIT_SPACE + 1;
 }
 
 impl IndexAccount {
-    pub fn initialize(&mut self, title: String, mint_list: Vec<Pubkey>, bump: u8) {
+    pub fn initialize(&mut self, title: String, curator: Pubkey, mint_list: Vec<Pubkey>, weights: Vec<u16>, share_mint: Pubkey, bump: u8) {
         self.title = title;
+        self.curator = curator;
+        self.backing = vec![0; mint_list.len()];
         self.mint_list = mint_list;
+        self.weights = weights;
+        self.share_mint = share_mint;
+        self.pause_state = PauseState::Active;
+        self.max_slippage_bps = DEFAULT_MAX_SLIPPAGE_BPS;
+        self.allowed_amms = Vec::new();
+        self.max_hops = DEFAULT_MAX_HOPS;
+        self.oracles = Vec::new();
+        self.price_guard = PriceGuard::default();
+        self.fees = FeeSettings { recipient: curator, ..FeeSettings::default() };
+        self.pending_fee_shares = 0;
+        self.fees_accrued_at = 0;
+        self.high_water_mark = 0;
+        self.keeper_bounty = KeeperBounty::None;
         self.bump = bump;
     }
 
+    pub fn space(title: usize, legs: usize, allowed_amms: usize, oracles: usize) -> usize {
+        IndexAccount::INIT_SPACE + title + legs * (32 + 2 + 8) + allowed_amms + oracles * 32
+    }
+
+    pub fn space_with_amms(&self, allowed_amms: usize) -> usize {
+        IndexAccount::space(self.title.len(), self.mint_list.len(), allowed_amms, self.oracles.len())
+    }
+
+    pub fn space_with_oracles(&self, oracles: usize) -> usize {
+        IndexAccount::space(self.title.len(), self.mint_list.len(), self.allowed_amms.len(), oracles)
+    }
+
+    // An Index without Oracles doesn't guard its Swaps against the Oracle price
+    pub fn set_price_guard(&mut self, oracles: Vec<Pubkey>, price_guard: PriceGuard) -> StdResult<(), NoviError> {
+        if !oracles.is_empty() && oracles.len() != self.mint_list.len() {
+            return Err(NoviError::InvalidOracleList);
+        }
+        if oracles.is_empty() && self.fees.performance_fee_bps > 0 {
+            return Err(NoviError::PerformanceFeeWithoutOracles);
+        }
+        price_guard.check_settings()?;
+
+        self.oracles = oracles;
+        self.price_guard = price_guard;
+        Ok(())
+    }
+
+    // Only AMMs the Swap enum knows can be allowed, each of them once
+    pub fn set_amm_allowlist(&mut self, allowed_amms: Vec<u8>, max_hops: u8) -> StdResult<(), NoviError> {
+        if max_hops == 0 {
+            return Err(NoviError::InvalidAmmAllowlist);
+        }
+        for (i, amm) in allowed_amms.iter().enumerate() {
+            if *amm >= Swap::VARIANTS || allowed_amms[..i].contains(amm) {
+                return Err(NoviError::InvalidAmmAllowlist);
+            }
+        }
+
+        self.allowed_amms = allowed_amms;
+        self.max_hops = max_hops;
+        Ok(())
+    }
+
+    // An empty allowlist lets the Swap route through any AMM, including ones
+    // newer than the Swap enum. Those stop the plan from being decoded past
+    // them, so a non-empty allowlist can't vouch for the route and rejects it.
+    pub fn check_route(&self, route_plan: &RoutePlan) -> Result<()> {
+        route_plan.check_structure()?;
+        require_gte!(self.max_hops as usize, route_plan.hops(), NoviError::RouteTooLong);
+
+        if !self.allowed_amms.is_empty() {
+            require!(route_plan.unknown_amm.is_none(), NoviError::AmmNotAllowed);
+            for step in &route_plan.steps {
+                require!(self.allowed_amms.contains(&step.amm()?), NoviError::AmmNotAllowed);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_fees(&mut self, fees: FeeSettings) -> StdResult<(), NoviError> {
+        fees.check_settings()?;
+        if fees.performance_fee_bps > 0 && self.oracles.is_empty() {
+            return Err(NoviError::PerformanceFeeWithoutOracles);
+        }
+        self.fees = fees;
+        Ok(())
+    }
+
+    // The minted Shares plus the fee Shares owed to the recipient
+    pub fn share_supply(&self, minted: u64) -> StdResult<u64, NoviError> {
+        minted.checked_add(self.pending_fee_shares).ok_or(NoviError::Overflow)
+    }
+
+    fn add_fee_shares(&mut self, shares: u64) -> StdResult<(), NoviError> {
+        self.pending_fee_shares = self.pending_fee_shares.checked_add(shares).ok_or(NoviError::Overflow)?;
+        Ok(())
+    }
+
+    // Has to run before anything changes the supply, so every holder pays for
+    // exactly the time they held their Shares
+    pub fn accrue_management_fee(&mut self, minted: u64, now: i64) -> StdResult<(), NoviError> {
+        let elapsed = now.saturating_sub(self.fees_accrued_at).max(0) as u64;
+        let fee_shares = self.fees.management_fee_shares(self.share_supply(minted)?, elapsed)?;
+        self.add_fee_shares(fee_shares)?;
+        self.fees_accrued_at = now;
+        Ok(())
+    }
+
+    // The performance fee only charges gains made after this valuation
+    pub fn start_high_water_mark(&mut self, share_value: u64, supply: u64, unit: u64) -> StdResult<(), NoviError> {
+        if supply > 0 {
+            self.high_water_mark = mul_div(share_value, unit, supply, false)?;
+        }
+        Ok(())
+    }
+
+    // The high-water mark starts with the first Shares, or when the fee gets
+    // turned on, so a zero one only means nobody has valued the Index yet
+    pub fn accrue_performance_fee(&mut self, share_value: u64, minted: u64, unit: u64) -> StdResult<(), NoviError> {
+        let supply = self.share_supply(minted)?;
+        if supply == 0 {
+            return Ok(());
+        }
+
+        let nav_per_share = mul_div(share_value, unit, supply, false)?;
+        if self.high_water_mark == 0 {
+            self.high_water_mark = nav_per_share;
+            return Ok(());
+        }
+
+        let fee_shares = self.fees.performance_fee_shares(share_value, supply, nav_per_share, self.high_water_mark, unit)?;
+        if fee_shares > 0 {
+            self.add_fee_shares(fee_shares)?;
+            self.high_water_mark = mul_div(share_value, unit, supply.checked_add(fee_shares).ok_or(NoviError::Overflow)?, false)?;
+        }
+        Ok(())
+    }
+
+    // Returns the Shares left to the depositor
+    pub fn charge_entry_fee(&mut self, shares: u64) -> StdResult<u64, NoviError> {
+        let fee_shares = FeeSettings::fee_shares(shares, self.fees.entry_fee_bps)?;
+        self.add_fee_shares(fee_shares)?;
+        Ok(shares - fee_shares)
+    }
+
+    // Returns the Shares actually redeemed, the fee stays in the Index
+    pub fn charge_exit_fee(&mut self, shares: u64) -> StdResult<u64, NoviError> {
+        let fee_shares = FeeSettings::fee_shares(shares, self.fees.exit_fee_bps)?;
+        self.add_fee_shares(fee_shares)?;
+        Ok(shares - fee_shares)
+    }
+
+    pub fn take_fee_shares(&mut self) -> StdResult<u64, NoviError> {
+        match std::mem::take(&mut self.pending_fee_shares) {
+            0 => Err(NoviError::NoFees),
+            shares => Ok(shares),
+        }
+    }
+
+    pub fn set_keeper_bounty(&mut self, keeper_bounty: KeeperBounty) -> StdResult<(), NoviError> {
+        keeper_bounty.check_settings()?;
+        self.keeper_bounty = keeper_bounty;
+        Ok(())
+    }
+
+    pub fn set_max_slippage(&mut self, max_slippage_bps: u16) -> StdResult<(), NoviError> {
+        if max_slippage_bps as u64 > TOTAL_WEIGHT_BPS {
+            return Err(NoviError::InvalidMaxSlippage);
+        }
+        self.max_slippage_bps = max_slippage_bps;
+        Ok(())
+    }
+
+    pub fn check_weights(mint_list: &[Pubkey], weights: &[u16]) -> StdResult<(), NoviError> {
+        if weights.len() != mint_list.len() {
+            return Err(NoviError::WeightsLengthMismatch);
+        }
+        if weights.contains(&0) || weights.iter().map(|&w| w as u64).sum::<u64>() != TOTAL_WEIGHT_BPS {
+            return Err(NoviError::InvalidWeights);
+        }
+        Ok(())
+    }
+
+    // Every leg is rounded down to its weight, the last leg picks up the dust
+    // so that the legs of a deposit always add up to the deposited amount.
+    pub fn leg_amount(&self, total: BaseAmount, mint_index: usize) -> StdResult<BaseAmount, NoviError> {
+        let weighted = |i: usize| mul_div(total.0, self.weights[i] as u64, TOTAL_WEIGHT_BPS, false);
+
+        if mint_index + 1 < self.mint_list.len() {
+            return weighted(mint_index).map(BaseAmount);
+        }
+
+        let mut allocated: u64 = 0;
+        for i in 0..mint_index {
+            allocated = allocated.checked_add(weighted(i)?).ok_or(NoviError::Overflow)?;
+        }
+        total.0.checked_sub(allocated).map(BaseAmount).ok_or(NoviError::Underflow)
+    }
+
     pub fn check_address(&self, mint: Pubkey) -> StdResult<usize, NoviError> {
         match self.mint_list.iter().position(|&m| m == mint) {
             Some(index) => Ok(index),
@@ -28,6 +294,44 @@ impl IndexAccount {
         }
     }
 
+    // Shares are priced from the holdings backing them: a deposit gets the
+    // smallest of its legs' pro-rata claims, and only the part of each leg
+    // needed to back those shares is wrapped. The first deposit into an
+    // empty index mints 1 share per unit deposited.
+    pub fn wrap_legs(&mut self, legs: &[u64], supply: u64, initial_shares: u64) -> StdResult<(u64, Vec<u64>), NoviError> {
+        if supply == 0 {
+            for (backing, &leg) in self.backing.iter_mut().zip(legs) {
+                *backing = backing.checked_add(leg).ok_or(NoviError::Overflow)?;
+            }
+            return Ok((initial_shares, legs.to_vec()));
+        }
+
+        let mut shares = u64::MAX;
+        for (&backing, &leg) in self.backing.iter().zip(legs) {
+            if backing > 0 {
+                shares = shares.min(mul_div(leg, supply, backing, false)?);
+            }
+        }
+
+        let mut used = Vec::with_capacity(legs.len());
+        for backing in self.backing.iter_mut() {
+            let amount = mul_div(shares, *backing, supply, true)?;
+            *backing = backing.checked_add(amount).ok_or(NoviError::Overflow)?;
+            used.push(amount);
+        }
+        Ok((shares, used))
+    }
+
+    pub fn unwrap_shares(&mut self, shares: u64, supply: u64) -> StdResult<Vec<u64>, NoviError> {
+        let mut released = Vec::with_capacity(self.backing.len());
+        for backing in self.backing.iter_mut() {
+            let amount = mul_div(shares, *backing, supply, false)?;
+            *backing = backing.checked_sub(amount).ok_or(NoviError::Underflow)?;
+            released.push(amount);
+        }
+        Ok(released)
+    }
+
     pub fn deposit<'info>(
         &self,
         amount: u64,
@@ -38,7 +342,7 @@ impl IndexAccount {
     }
 
     pub fn withdraw<'info>(
-        &mut self,
+        &self,
         amount: u64,
         accounts: Transfer<'info>,
         program: AccountInfo<'info>,
@@ -46,4 +350,4 @@ impl IndexAccount {
     ) -> Result<()> {
         transfer(CpiContext::new_with_signer(program, accounts, signer_seeds), amount)
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,10 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use std::result::Result as StdResult;
+
+use crate::errors::NoviError;
 
 #[account]
 pub struct IndexProfile {
@@ -9,4 +15,89 @@ pub struct IndexProfile {
 
 impl Space for IndexProfile {
     const INIT_SPACE: usize = 8 + 32 + 4 + 1;
-}
\ No newline at end of file
+}
+
+impl IndexProfile {
+    pub fn space(legs: usize) -> usize {
+        IndexProfile::INIT_SPACE + legs * 8
+    }
+
+    // A Profile created through init_if_needed starts out zeroed
+    pub fn initialize_if_needed(&mut self, owner: Pubkey, legs: usize, bump: u8) {
+        if self.owner == Pubkey::default() {
+            self.owner = owner;
+            self.mint_amount = vec![0; legs];
+            self.bump = bump;
+        }
+    }
+
+    // Grow a Profile that has fewer balances than the Index has Mints, the
+    // payer tops up the rent for the extra space.
+    pub fn realloc<'info>(
+        profile: &mut Account<'info, IndexProfile>,
+        legs: usize,
+        payer: AccountInfo<'info>,
+        program: AccountInfo<'info>,
+    ) -> Result<()> {
+        if profile.mint_amount.len() >= legs {
+            return Ok(());
+        }
+
+        let space = IndexProfile::space(legs);
+        let info = profile.to_account_info();
+        let lamports = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+        if lamports > 0 {
+            transfer(CpiContext::new(program, Transfer { from: payer, to: info.clone() }), lamports)?;
+        }
+        info.realloc(space, false)?;
+
+        profile.mint_amount.resize(legs, 0);
+        Ok(())
+    }
+
+    pub fn credit_leg(&mut self, mint_index: usize, amount: u64) -> StdResult<(), NoviError> {
+        let balance = &mut self.mint_amount[mint_index];
+        *balance = balance.checked_add(amount).ok_or(NoviError::Overflow)?;
+        Ok(())
+    }
+
+    pub fn debit_leg(&mut self, mint_index: usize, amount: u64) -> StdResult<(), NoviError> {
+        let balance = &mut self.mint_amount[mint_index];
+        *balance = balance.checked_sub(amount).ok_or(NoviError::InsufficientProfileBalance)?;
+        Ok(())
+    }
+
+    // One amount per Mint of the Index, in the order of its mint_list
+    pub fn credit(&mut self, amounts: &[u64]) -> StdResult<(), NoviError> {
+        for (mint_index, &amount) in amounts.iter().enumerate() {
+            self.credit_leg(mint_index, amount)?;
+        }
+        Ok(())
+    }
+
+    pub fn debit(&mut self, amounts: &[u64]) -> StdResult<(), NoviError> {
+        for (mint_index, &amount) in amounts.iter().enumerate() {
+            self.debit_leg(mint_index, amount)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credits_and_debits_per_mint() {
+        let mut profile = IndexProfile { owner: Pubkey::default(), mint_amount: vec![], bump: 0 };
+        profile.initialize_if_needed(Pubkey::new_unique(), 2, 255);
+
+        profile.credit(&[100, 50]).unwrap();
+        profile.credit_leg(1, 25).unwrap();
+        profile.debit(&[40, 75]).unwrap();
+        assert_eq!(profile.mint_amount, vec![60, 0]);
+
+        assert!(matches!(profile.debit_leg(1, 1), Err(NoviError::InsufficientProfileBalance)));
+        assert!(matches!(profile.credit_leg(0, u64::MAX), Err(NoviError::Overflow)));
+    }
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::NoviError;
+
+// A registered keeper and the lamport bond it posted. The bond sits on top of
+// the rent of this account and backs every leg the keeper swaps.
+#[account]
+pub struct KeeperAccount {
+    pub authority: Pubkey,
+    pub bond: u64,
+    pub slashed: u64,
+    pub last_swap_slot: u64,
+    // Swaps so far, numbering the Disputes a Swap can open
+    pub swaps: u64,
+    pub bump: u8,
+}
+
+impl Space for KeeperAccount {
+    const INIT_SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1;
+}
+
+impl KeeperAccount {
+    // A Keeper created through init_if_needed starts out zeroed
+    pub fn initialize_if_needed(&mut self, authority: Pubkey, bump: u8) {
+        if self.authority == Pubkey::default() {
+            self.authority = authority;
+            self.bump = bump;
+        }
+    }
+
+    pub fn add_bond(&mut self, amount: u64) -> Result<()> {
+        self.bond = self.bond.checked_add(amount).ok_or(NoviError::Overflow)?;
+        Ok(())
+    }
+
+    pub fn check_bond(&self, min_keeper_bond: u64) -> Result<()> {
+        require_gte!(self.bond, min_keeper_bond, NoviError::InsufficientBond);
+        Ok(())
+    }
+
+    // Returns the nonce of the Swap
+    pub fn record_swap(&mut self) -> Result<u64> {
+        let nonce = self.swaps;
+        self.swaps = self.swaps.checked_add(1).ok_or(NoviError::Overflow)?;
+        self.last_swap_slot = Clock::get()?.slot;
+        Ok(nonce)
+    }
+
+    // The bond stays locked for a dispute window after the last swap, long
+    // enough for the deposits it touched to become refundable
+    pub fn withdraw_bond(&mut self, amount: u64, dispute_window_slots: u64) -> Result<()> {
+        let unlock_slot = self.last_swap_slot.checked_add(dispute_window_slots).ok_or(NoviError::Overflow)?;
+        require!(Clock::get()?.slot >= unlock_slot, NoviError::BondLocked);
+
+        self.bond = self.bond.checked_sub(amount).ok_or(NoviError::InsufficientBond)?;
+        Ok(())
+    }
+
+    pub fn slash(&mut self, amount: u64) -> Result<()> {
+        self.bond = self.bond.checked_sub(amount).ok_or(NoviError::InsufficientBond)?;
+        self.slashed = self.slashed.checked_add(amount).ok_or(NoviError::Overflow)?;
+        Ok(())
+    }
+}
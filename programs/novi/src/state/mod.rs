@@ -4,5 +4,20 @@ pub use deposit_account::*;
 pub mod index;
 pub use index::*;
 
+pub mod fees;
+pub use fees::*;
+
 pub mod index_profile;
-pub use index_profile::*;
\ No newline at end of file
+pub use index_profile::*;
+
+pub mod swap_ticket;
+pub use swap_ticket::*;
+
+pub mod swap_dispute;
+pub use swap_dispute::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod keeper;
+pub use keeper::*;
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+// Evidence that a Keeper delivered a leg below the price band of the Oracles,
+// opened by Finalize and settled by SlashKeeper. Keyed by the Keeper and the
+// nonce of the Swap, so it never stands in the way of another Swap Ticket.
+#[account]
+pub struct SwapDispute {
+    // The Keeper authority that swapped the leg
+    pub keeper: Pubkey,
+    // Owner of the Deposit, compensated by the slash
+    pub owner: Pubkey,
+    pub payer: Pubkey,
+    pub deposit_mint: Pubkey,
+    // What the leg fell short of the band by, in the deposit Mint
+    pub shortfall: u64,
+    pub bump: u8,
+}
+
+impl Space for SwapDispute {
+    const INIT_SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 1;
+}
+
+impl SwapDispute {
+    pub fn initialize(&mut self, keeper: Pubkey, owner: Pubkey, payer: Pubkey, deposit_mint: Pubkey, shortfall: u64, bump: u8) {
+        self.keeper = keeper;
+        self.owner = owner;
+        self.payer = payer;
+        self.deposit_mint = deposit_mint;
+        self.shortfall = shortfall;
+        self.bump = bump;
+    }
+}
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+
+use crate::{amounts::mul_div, constants::TOTAL_WEIGHT_BPS, errors::NoviError};
+
+// One pending swap leg: opened by InitializeSwap and consumed by Finalize,
+// which closes it.
+#[account]
+pub struct SwapTicket {
+    pub deposit: Pubkey,
+    pub owner: Pubkey,
+    pub payer: Pubkey,
+    // The Keeper authority swapping this leg
+    pub keeper: Pubkey,
+    // The Keeper's nonce for this Swap, which keys its Dispute
+    pub nonce: u64,
+    pub mint: Pubkey,
+    pub deposit_mint: Pubkey,
+    pub in_amount: u64,
+    pub quoted_out_amount: u64,
+    pub min_out_amount: u64,
+    pub balance: u64,
+    // The swapper's balance of the deposit Mint before the leg came in
+    pub input_balance: u64,
+    pub bump: u8,
+}
+
+impl Space for SwapTicket {
+    const INIT_SPACE: usize = 8 + 32 + 32 + 32 + 32 + 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+impl SwapTicket {
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(&mut self, deposit: Pubkey, owner: Pubkey, payer: Pubkey, keeper: Pubkey, nonce: u64, mint: Pubkey, deposit_mint: Pubkey, in_amount: u64, bump: u8) {
+        self.deposit = deposit;
+        self.owner = owner;
+        self.payer = payer;
+        self.keeper = keeper;
+        self.nonce = nonce;
+        self.mint = mint;
+        self.deposit_mint = deposit_mint;
+        self.in_amount = in_amount;
+        self.bump = bump;
+    }
+
+    // Snapshot the swapper's balances before the swap, so Finalize can
+    // measure what the swap actually delivered and what it left unspent.
+    pub fn record_quote(&mut self, input_balance: u64, balance: u64, quoted_out_amount: u64, slippage_bps: u16) -> Result<()> {
+        let keep_bps = TOTAL_WEIGHT_BPS.checked_sub(slippage_bps as u64).ok_or(NoviError::InvalidSlippage)?;
+
+        self.balance = balance;
+        self.input_balance = input_balance;
+        self.quoted_out_amount = quoted_out_amount;
+        self.min_out_amount = mul_div(quoted_out_amount, keep_bps, TOTAL_WEIGHT_BPS, true)?;
+        Ok(())
+    }
+
+    pub fn swapped_amount(&self, balance: u64) -> Result<u64> {
+        let received = balance.checked_sub(self.balance).ok_or(NoviError::Underflow)?;
+        require_gte!(received, self.min_out_amount, NoviError::InsufficientSwapOutput);
+        Ok(received)
+    }
+
+    pub fn unspent_amount(&self, input_balance: u64) -> Result<u64> {
+        Ok(input_balance.checked_sub(self.input_balance).ok_or(NoviError::Underflow)?)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_no_less_than_the_quote_minus_slippage() {
+        let key = Pubkey::default();
+        let mut ticket = SwapTicket { deposit: key, owner: key, payer: key, keeper: key, nonce: 0, mint: key, deposit_mint: key, in_amount: 0, quoted_out_amount: 0, min_out_amount: 0, balance: 0, input_balance: 0, bump: 0 };
+        ticket.initialize(key, key, key, key, 7, key, key, 1_000_000, 255);
+        ticket.record_quote(0, 50_000, 200_000, 50).unwrap();
+
+        // 0.5% under the quote is as low as it goes
+        assert_eq!(ticket.swapped_amount(249_000).unwrap(), 199_000);
+        assert_eq!(ticket.swapped_amount(300_000).unwrap(), 250_000);
+        assert!(ticket.swapped_amount(248_999).is_err());
+    }
+
+    #[test]
+    fn measures_what_the_swap_left_unspent() {
+        let key = Pubkey::default();
+        let mut ticket = SwapTicket { deposit: key, owner: key, payer: key, keeper: key, nonce: 0, mint: key, deposit_mint: key, in_amount: 0, quoted_out_amount: 0, min_out_amount: 0, balance: 0, input_balance: 0, bump: 0 };
+        ticket.record_quote(30_000, 0, 200_000, 0).unwrap();
+
+        assert_eq!(ticket.unspent_amount(30_000).unwrap(), 0);
+        assert_eq!(ticket.unspent_amount(130_000).unwrap(), 100_000);
+        assert!(ticket.unspent_amount(29_999).is_err());
+    }
+}
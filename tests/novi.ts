@@ -1,19 +1,1913 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
+import {
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  NATIVE_MINT,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createMint,
+  createSyncNativeInstruction,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
+import { assert } from "chai";
 import { Novi } from "../target/types/novi";
 
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+const METADATA_PROGRAM_ID = new PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+);
+const JUPITER_PROGRAM_ID = new PublicKey(
+  "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"
+);
+const USDC_MINT = new PublicKey(
+  "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+);
+const SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR = Buffer.from([
+  0xc1, 0x20, 0x9b, 0x33, 0x41, 0xd6, 0x9c, 0x81,
+]);
+const SHARED_ACCOUNTS_EXACT_OUT_ROUTE_DISCRIMINATOR = Buffer.from([
+  0xb0, 0xd1, 0x69, 0xa8, 0x9a, 0x7d, 0x45, 0x3e,
+]);
+// Borsh variant indexes of Jupiter's Swap enum
+const RAYDIUM = 7;
+const WHIRLPOOL = 17;
+const RAYDIUM_CLMM = 26;
+const PHOENIX = 28;
+const METEORA_DLMM = 38;
+// Static Pyth price updates cloned into the validator, see Anchor.toml
+const SOL_ORACLE = new PublicKey(
+  "aLSwdVckkDcw9FsoPs9osR2kT35HSAhaBu6GXRpUrHr"
+);
+const LEG_ORACLES = [
+  new PublicKey("8N1Ri3LHCFFcveqGumSfNQiMyw4HMvzfLsStjvui1V4g"),
+  new PublicKey("J3n796eS8NFEF31jppFr8NCVr33fqdBe7WVnaShHQVLE"),
+];
+// Leg A at $2,700 instead of $1,800
+const LEG_A_UP_ORACLE = new PublicKey(
+  "3TLVtNTUtpp5kiSFMS4G2W1ePv6VpgKBG2jfbS7E85tj"
+);
+// The fixture prices never move, so they're never too old for these guards
+const STATIC_PRICE_GUARD = {
+  bandBps: 100,
+  maxAgeSecs: 4_294_967_295,
+  maxConfidenceBps: 50,
+};
+
 describe("novi", () => {
-  const wallet = anchor.Wallet.local();
-  const provider = anchor.getProvider();
-  const connection = provider.connection;
-  // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
 
+  const provider = anchor.getProvider() as anchor.AnchorProvider;
+  const connection = provider.connection;
+  const wallet = provider.wallet as anchor.Wallet;
   const program = anchor.workspace.Novi as Program<Novi>;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+  const admin = Keypair.generate();
+  const title = "novi-e2e";
+  const seed = new anchor.BN(1);
+  const amount = new anchor.BN(2);
+  const weights = [6_000, 4_000];
+
+  let mintList: PublicKey[];
+  let legAmount: anchor.BN;
+
+  const [config] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [programData] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+  );
+  const [index] = PublicKey.findProgramAddressSync(
+    [Buffer.from("index"), Buffer.from(title)],
+    program.programId
+  );
+  const [deposit] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("deposit"),
+      seed.toArrayLike(Buffer, "le", 8),
+      wallet.publicKey.toBuffer(),
+    ],
+    program.programId
+  );
+  const [shareMint] = PublicKey.findProgramAddressSync(
+    [Buffer.from("shares"), index.toBuffer()],
+    program.programId
+  );
+  const [shareMetadata] = PublicKey.findProgramAddressSync(
+    [Buffer.from("metadata"), METADATA_PROGRAM_ID.toBuffer(), shareMint.toBuffer()],
+    METADATA_PROGRAM_ID
+  );
+  const [indexProfile] = PublicKey.findProgramAddressSync(
+    [Buffer.from("profile"), index.toBuffer(), wallet.publicKey.toBuffer()],
+    program.programId
+  );
+  const keeperFor = (authority: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("keeper"), authority.toBuffer()],
+      program.programId
+    )[0];
+  const keeper = keeperFor(wallet.publicKey);
+
+  const bondKeeper = (lamports: number, authority = wallet.payer) =>
+    program.methods
+      .bondKeeper(new anchor.BN(lamports))
+      .accounts({
+        authority: authority.publicKey,
+        keeper: keeperFor(authority.publicKey),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(authority === wallet.payer ? [] : [authority])
+      .rpc();
+
+  before(async () => {
+    // The admin pays for the Config and Index reallocs it signs
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: wallet.publicKey,
+          toPubkey: admin.publicKey,
+          lamports: 10 * LAMPORTS_PER_SOL,
+        })
+      )
+    );
+
+    mintList = [];
+    for (let i = 0; i < 2; i++) {
+      mintList.push(
+        await createMint(connection, wallet.payer, wallet.publicKey, null, 6)
+      );
+    }
+  });
+
+  it("Initializes the config", async () => {
+    await program.methods
+      .initializeConfig(admin.publicKey)
+      .accounts({
+        authority: wallet.publicKey,
+        config,
+        program: program.programId,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .setDepositMint(NATIVE_MINT, new anchor.BN(1), new anchor.BN(1_000))
+      .accounts({
+        admin: admin.publicKey,
+        config,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    const account = await program.account.config.fetch(config);
+    assert.ok(account.admin.equals(admin.publicKey));
+    assert.equal(account.depositMints.length, 1);
+    assert.ok(account.depositMints[0].mint.equals(NATIVE_MINT));
+  });
+
+  it("Only lets the admin update the config", async () => {
+    try {
+      await program.methods
+        .proposeAdmin(wallet.publicKey)
+        .accounts({ admin: wallet.publicKey, config })
+        .rpc();
+      assert.fail("propose_admin should be gated by the admin");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "PrivilageEscalated"
+      );
+    }
+  });
+
+  it("Hands the admin over in two steps", async () => {
+    const nextAdmin = Keypair.generate();
+    const handover = async (from: Keypair, to: Keypair) => {
+      await program.methods
+        .proposeAdmin(to.publicKey)
+        .accounts({ admin: from.publicKey, config })
+        .signers([from])
+        .rpc();
+      await program.methods
+        .acceptAdmin()
+        .accounts({ pendingAdmin: to.publicKey, config })
+        .signers([to])
+        .rpc();
+    };
+
+    await handover(admin, nextAdmin);
+    assert.ok(
+      (await program.account.config.fetch(config)).admin.equals(
+        nextAdmin.publicKey
+      )
+    );
+
+    await handover(nextAdmin, admin);
+    const account = await program.account.config.fetch(config);
+    assert.ok(account.admin.equals(admin.publicKey));
+    assert.isNull(account.pendingAdmin);
+  });
+
+  it("Grants the pauser role", async () => {
+    await program.methods
+      .grantRole({ pauser: {} }, wallet.publicKey)
+      .accounts({
+        admin: admin.publicKey,
+        config,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    const account = await program.account.config.fetch(config);
+    assert.equal(account.roles.length, 1);
+    assert.ok(account.roles[0].authority.equals(wallet.publicKey));
+  });
+
+  it("Registers the keeper with a bond", async () => {
+    await bondKeeper(LAMPORTS_PER_SOL / 2);
+    await bondKeeper(LAMPORTS_PER_SOL / 2);
+
+    const account = await program.account.keeperAccount.fetch(keeper);
+    assert.ok(account.authority.equals(wallet.publicKey));
+    assert.equal(account.bond.toNumber(), LAMPORTS_PER_SOL);
+  });
+
+  it("Sets where swap surplus goes", async () => {
+    const setSurplusPolicy = (surplusPolicy: object) =>
+      program.methods
+        .setSurplusPolicy(surplusPolicy as any)
+        .accounts({ admin: admin.publicKey, config })
+        .signers([admin])
+        .rpc();
+
+    assert.deepEqual(
+      (await program.account.config.fetch(config)).surplusPolicy,
+      { vault: {} }
+    );
+    await setSurplusPolicy({ keeper: {} });
+    assert.deepEqual(
+      (await program.account.config.fetch(config)).surplusPolicy,
+      { keeper: {} }
+    );
+    await setSurplusPolicy({ vault: {} });
+  });
+
+  it("Creates an index", async () => {
+    await program.methods
+      .createIndex(title, mintList, weights, "NOVI", "")
+      .accounts({
+        curator: admin.publicKey,
+        payer: wallet.publicKey,
+        config,
+        index,
+        shareMint,
+        shareMetadata,
+        tokenMetadataProgram: METADATA_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([admin])
+      .rpc();
+
+    const account = await program.account.indexAccount.fetch(index);
+    assert.equal(account.title, title);
+    assert.deepEqual(
+      account.mintList.map((m) => m.toBase58()),
+      mintList.map((m) => m.toBase58())
+    );
+    assert.deepEqual(account.weights, weights);
+    assert.ok(account.shareMint.equals(shareMint));
+    assert.ok(account.curator.equals(admin.publicKey));
+  });
+
+  it("Refuses weights that don't add up to 10_000 bps", async () => {
+    const [badIndex] = PublicKey.findProgramAddressSync(
+      [Buffer.from("index"), Buffer.from("novi-bad-weights")],
+      program.programId
+    );
+    const [badShareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from("shares"), badIndex.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .createIndex("novi-bad-weights", mintList, [5_000, 4_000], "BAD", "")
+        .accounts({
+          curator: admin.publicKey,
+          payer: wallet.publicKey,
+          config,
+          index: badIndex,
+          shareMint: badShareMint,
+          shareMetadata: PublicKey.findProgramAddressSync(
+            [
+              Buffer.from("metadata"),
+              METADATA_PROGRAM_ID.toBuffer(),
+              badShareMint.toBuffer(),
+            ],
+            METADATA_PROGRAM_ID
+          )[0],
+          tokenMetadataProgram: METADATA_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([admin])
+        .rpc();
+      assert.fail("create_index should validate the weights");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "InvalidWeights"
+      );
+    }
+  });
+
+  const depositFor = (depositSeed: anchor.BN) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("deposit"),
+        depositSeed.toArrayLike(Buffer, "le", 8),
+        wallet.publicKey.toBuffer(),
+      ],
+      program.programId
+    )[0];
+
+  const bountyFor = (depositAccount: PublicKey) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("bounty"), depositAccount.toBuffer()],
+      program.programId
+    )[0];
+
+  const depositWith = (
+    depositSeed: anchor.BN,
+    native = false,
+    maxSlippageBps: number | null = null,
+    usdcBounty = false
+  ) => {
+    const depositAccount = depositFor(depositSeed);
+    return program.methods
+      .deposit(depositSeed, amount, maxSlippageBps)
+      .accounts({
+      user: wallet.publicKey,
+      payer: wallet.publicKey,
+      config,
+      deposit: depositAccount,
+      index,
+      mint: NATIVE_MINT,
+      depositToken: getAssociatedTokenAddressSync(
+        NATIVE_MINT,
+        depositAccount,
+        true
+      ),
+      userToken: native
+        ? null
+        : getAssociatedTokenAddressSync(NATIVE_MINT, wallet.publicKey),
+      usdc: usdcBounty ? USDC_MINT : null,
+      payerUsdc: usdcBounty
+        ? getAssociatedTokenAddressSync(USDC_MINT, wallet.publicKey)
+        : null,
+      bountyToken: usdcBounty ? bountyFor(depositAccount) : null,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+  };
+
+  it("Deposits wSOL into the index", async () => {
+    const userToken = getAssociatedTokenAddressSync(
+      NATIVE_MINT,
+      wallet.publicKey
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        createAssociatedTokenAccountIdempotentInstruction(
+          wallet.publicKey,
+          userToken,
+          wallet.publicKey,
+          NATIVE_MINT
+        ),
+        SystemProgram.transfer({
+          fromPubkey: wallet.publicKey,
+          toPubkey: userToken,
+          lamports: 10 * LAMPORTS_PER_SOL,
+        }),
+        createSyncNativeInstruction(userToken)
+      )
+    );
+
+    await depositWith(seed).rpc();
+
+    const account = await program.account.depositAccount.fetch(deposit);
+    assert.ok(account.owner.equals(wallet.publicKey));
+    assert.ok(account.mint.equals(NATIVE_MINT));
+    assert.equal(
+      account.amount.toString(),
+      amount.muln(LAMPORTS_PER_SOL).toString()
+    );
+    assert.deepEqual(account.mintList, [false, false]);
+    legAmount = account.amount.muln(weights[0]).divn(10_000);
+  });
+
+  it("Wraps native SOL deposits into the vault", async () => {
+    const nativeSeed = new anchor.BN(4);
+    await depositWith(nativeSeed, true).rpc();
+    const nativeDeposit = depositFor(nativeSeed);
+
+    const account = await program.account.depositAccount.fetch(nativeDeposit);
+    const vault = await connection.getTokenAccountBalance(
+      getAssociatedTokenAddressSync(NATIVE_MINT, nativeDeposit, true)
+    );
+    assert.equal(vault.value.amount, account.amount.toString());
+  });
+
+  const setIndexPause = (pauseState: object) =>
+    program.methods
+      .setIndexPause(pauseState as any)
+      .accounts({ pauser: admin.publicKey, config, index })
+      .signers([admin])
+      .rpc();
+
+  it("Blocks deposits while the index is redemption-only", async () => {
+    await setIndexPause({ redeemOnly: {} });
+    try {
+      await depositWith(new anchor.BN(2)).rpc();
+      assert.fail("deposit should be blocked while redemption-only");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "RedeemOnly"
+      );
+    } finally {
+      await setIndexPause({ active: {} });
+    }
+  });
+
+  const refund = (depositAccount: PublicKey) =>
+    program.methods
+      .refund()
+      .accounts({
+        owner: wallet.publicKey,
+        payer: wallet.publicKey,
+        config,
+        deposit: depositAccount,
+        index,
+        mint: NATIVE_MINT,
+        depositToken: getAssociatedTokenAddressSync(
+          NATIVE_MINT,
+          depositAccount,
+          true
+        ),
+        ownerToken: getAssociatedTokenAddressSync(
+          NATIVE_MINT,
+          wallet.publicKey
+        ),
+        bountyToken: null,
+        payerUsdc: null,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+  it("Refunds an un-swapped deposit", async () => {
+    const refundSeed = new anchor.BN(3);
+    await depositWith(refundSeed).rpc();
+    const refundDeposit = depositFor(refundSeed);
+
+    try {
+      await refund(refundDeposit);
+      assert.fail("refund should wait for the refund delay");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "RefundLocked"
+      );
+    }
+
+    await setIndexPause({ redeemOnly: {} });
+    try {
+      await refund(refundDeposit);
+    } finally {
+      await setIndexPause({ active: {} });
+    }
+    assert.isNull(await connection.getAccountInfo(refundDeposit));
+  });
+
+  const ticketFor = (legMint: PublicKey, depositAccount = deposit) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), depositAccount.toBuffer(), legMint.toBuffer()],
+      program.programId
+    )[0];
+
+  type Oracles = {
+    inputOracle: PublicKey | null;
+    outputOracle: PublicKey | null;
+  };
+  const noOracles: Oracles = { inputOracle: null, outputOracle: null };
+  const disputeFor = (nonce: anchor.BN, authority = wallet.publicKey) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("dispute"),
+        authority.toBuffer(),
+        nonce.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  const initializeSwap = (
+    legMint: PublicKey,
+    inAmount: anchor.BN,
+    depositAccount = deposit,
+    oracles = noOracles,
+    swapper = wallet.publicKey
+  ) =>
+    program.methods.initializeSwap(inAmount).accounts({
+      swapper,
+      payer: wallet.publicKey,
+      config,
+      deposit: depositAccount,
+      index,
+      keeper: keeperFor(swapper),
+      ticket: ticketFor(legMint, depositAccount),
+      depositMint: NATIVE_MINT,
+      depositToken: getAssociatedTokenAddressSync(
+        NATIVE_MINT,
+        depositAccount,
+        true
+      ),
+      swapperToken: getAssociatedTokenAddressSync(NATIVE_MINT, swapper),
+      mint: legMint,
+      swapperMintToken: getAssociatedTokenAddressSync(legMint, swapper),
+      tokenLedger: null,
+      ...oracles,
+      instructionsSysvarProgram: SYSVAR_INSTRUCTIONS_PUBKEY,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+  type FinalizeOptions = {
+    usdcBounty?: boolean;
+    oracles?: Oracles;
+    dispute?: PublicKey | null;
+  };
+  const finalize = (
+    legMint: PublicKey,
+    depositAccount = deposit,
+    {
+      usdcBounty = false,
+      oracles = noOracles,
+      dispute = null,
+    }: FinalizeOptions = {}
+  ) =>
+    program.methods.finalize().accounts({
+      swapper: wallet.publicKey,
+      owner: wallet.publicKey,
+      payer: wallet.publicKey,
+      config,
+      index,
+      indexProfile,
+      deposit: depositAccount,
+      ticket: ticketFor(legMint, depositAccount),
+      mint: legMint,
+      depositMint: NATIVE_MINT,
+      indexToken: getAssociatedTokenAddressSync(legMint, index, true),
+      swapperToken: getAssociatedTokenAddressSync(legMint, wallet.publicKey),
+      swapperDepositToken: getAssociatedTokenAddressSync(
+        NATIVE_MINT,
+        wallet.publicKey
+      ),
+      depositToken: getAssociatedTokenAddressSync(
+        NATIVE_MINT,
+        depositAccount,
+        true
+      ),
+      shareMint,
+      ownerShares: getAssociatedTokenAddressSync(shareMint, wallet.publicKey),
+      depositPayer: wallet.publicKey,
+      bountyToken: usdcBounty ? bountyFor(depositAccount) : null,
+      swapperUsdc: usdcBounty
+        ? getAssociatedTokenAddressSync(USDC_MINT, wallet.publicKey)
+        : null,
+      ...oracles,
+      dispute,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    });
+
+  // The stub deployed at the Jupiter address settles a SharedAccountsRoute
+  // at the quoted rate less the platform fee, out of its pre-funded
+  // destination account.
+  const [jupiterAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("authority"), Buffer.from([0])],
+    JUPITER_PROGRAM_ID
+  );
+
+  const sharedAccountsRoute = (
+    outputMint: PublicKey,
+    inAmount: anchor.BN,
+    quotedOutAmount: anchor.BN,
+    slippageBps: number,
+    destination = getAssociatedTokenAddressSync(outputMint, wallet.publicKey),
+    inputMint = NATIVE_MINT,
+    swapper = wallet.publicKey,
+    platformFeeBps = 0
+  ) => {
+    // A single Whirlpool step taking 100% of position 0 to position 1
+    const routePlan = Buffer.from([1, 0, 0, 0, WHIRLPOOL, 1, 100, 0, 1]);
+    const data = Buffer.alloc(8 + 1 + routePlan.length + 8 + 8 + 2 + 1);
+    SHARED_ACCOUNTS_ROUTE_DISCRIMINATOR.copy(data, 0);
+    data.writeUInt8(0, 8);
+    routePlan.copy(data, 9);
+    inAmount.toArrayLike(Buffer, "le", 8).copy(data, 18);
+    quotedOutAmount.toArrayLike(Buffer, "le", 8).copy(data, 26);
+    data.writeUInt16LE(slippageBps, 34);
+    data.writeUInt8(platformFeeBps, 36);
+
+    const keys = [
+      TOKEN_PROGRAM_ID,
+      jupiterAuthority,
+      swapper,
+      getAssociatedTokenAddressSync(inputMint, swapper),
+      getAssociatedTokenAddressSync(inputMint, jupiterAuthority, true),
+      getAssociatedTokenAddressSync(outputMint, jupiterAuthority, true),
+      destination,
+      inputMint,
+      outputMint,
+    ].map((pubkey, i) => ({
+      pubkey,
+      isSigner: i === 2,
+      isWritable: i >= 3 && i <= 6,
+    }));
+    return new TransactionInstruction({
+      programId: JUPITER_PROGRAM_ID,
+      keys,
+      data,
+    });
+  };
+
+  type SwapOptions = {
+    slippageBps?: number;
+    oracles?: Oracles;
+    usdcBounty?: boolean;
+    platformFeeBps?: number;
+    // Finalize opens the dispute of a leg delivered below the price band
+    openDispute?: boolean;
+  };
+  // Returns the dispute the swap opened, if any
+  // Takes the same accounts, with out_amount and quoted_in_amount where the
+  // route has in_amount and quoted_out_amount
+  const sharedAccountsExactOutRoute = (
+    outputMint: PublicKey,
+    outAmount: anchor.BN,
+    quotedInAmount: anchor.BN,
+    slippageBps: number
+  ) => {
+    const ix = sharedAccountsRoute(
+      outputMint,
+      outAmount,
+      quotedInAmount,
+      slippageBps
+    );
+    SHARED_ACCOUNTS_EXACT_OUT_ROUTE_DISCRIMINATOR.copy(ix.data, 0);
+    return ix;
+  };
+
+  const swapLeg = async (
+    legMint: PublicKey,
+    inAmount: anchor.BN,
+    quotedOutAmount: number,
+    depositAccount = deposit,
+    {
+      slippageBps = 50,
+      oracles = noOracles,
+      usdcBounty = false,
+      platformFeeBps = 0,
+      openDispute = false,
+    }: SwapOptions = {}
+  ) => {
+    for (const mint of [NATIVE_MINT, legMint]) {
+      await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        mint,
+        jupiterAuthority,
+        true
+      );
+    }
+    await mintTo(
+      connection,
+      wallet.payer,
+      legMint,
+      getAssociatedTokenAddressSync(legMint, jupiterAuthority, true),
+      wallet.publicKey,
+      quotedOutAmount
+    );
+    const dispute = openDispute
+      ? disputeFor((await program.account.keeperAccount.fetch(keeper)).swaps)
+      : null;
+
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        await initializeSwap(
+          legMint,
+          inAmount,
+          depositAccount,
+          oracles
+        ).instruction(),
+        sharedAccountsRoute(
+          legMint,
+          inAmount,
+          new anchor.BN(quotedOutAmount),
+          slippageBps,
+          undefined,
+          undefined,
+          undefined,
+          platformFeeBps
+        ),
+        await finalize(legMint, depositAccount, {
+          usdcBounty,
+          oracles,
+          dispute,
+        }).instruction()
+      )
+    );
+    return dispute;
+  };
+
+  it("Refuses to swap without a Jupiter instruction", async () => {
+    try {
+      await initializeSwap(mintList[0], legAmount).rpc();
+      assert.fail("initialize_swap should require a swap instruction");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "MissingSwapIx"
+      );
+    }
+  });
+
+  it("Refuses to finalize without a swap ticket", async () => {
+    try {
+      await finalize(mintList[0]).rpc();
+      assert.fail("finalize should consume a swap ticket");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "AccountNotInitialized"
+      );
+    }
+  });
+
+  it("Refuses swaps from unregistered or under-bonded keepers", async () => {
+    const stranger = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: wallet.publicKey,
+          toPubkey: stranger.publicKey,
+          lamports: LAMPORTS_PER_SOL,
+        })
+      )
+    );
+    const swapAsStranger = () =>
+      initializeSwap(
+        mintList[0],
+        legAmount,
+        deposit,
+        noOracles,
+        stranger.publicKey
+      )
+        .signers([stranger])
+        .rpc();
+
+    try {
+      await swapAsStranger();
+      assert.fail("the stranger never registered as a keeper");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "AccountNotInitialized"
+      );
+    }
+
+    // Half the minimum bond
+    await bondKeeper(LAMPORTS_PER_SOL / 2, stranger);
+    try {
+      await swapAsStranger();
+      assert.fail("the stranger's bond is too small");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "InsufficientBond"
+      );
+    }
+  });
+
+  it("Finalizes the first leg into the index profile", async () => {
+    await swapLeg(mintList[0], legAmount, 1_000_000);
+
+    const profile = await program.account.indexProfile.fetch(indexProfile);
+    assert.ok(profile.owner.equals(wallet.publicKey));
+    assert.equal(profile.mintAmount[0].toNumber(), 1_000_000);
+    assert.equal(profile.mintAmount[1].toNumber(), 0);
+    assert.isNull(await connection.getAccountInfo(ticketFor(mintList[0])));
+
+    const account = await program.account.depositAccount.fetch(deposit);
+    assert.deepEqual(account.mintList, [true, false]);
+    assert.deepEqual(account.finalized, [true, false]);
+    assert.equal(
+      account.remaining.toString(),
+      account.amount.sub(legAmount).toString()
+    );
+  });
+
+  it("Refuses to swap the same leg twice", async () => {
+    try {
+      await initializeSwap(mintList[0], legAmount).rpc();
+      assert.fail("a swapped leg should not be released again");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "AlreadySwapped"
+      );
+    }
+  });
+
+  it("Wraps the deposit into shares once every leg is finalized", async () => {
+    const { amount: deposited } = await program.account.depositAccount.fetch(
+      deposit
+    );
+    await swapLeg(mintList[1], deposited.sub(legAmount), 500_000);
+
+    const profile = await program.account.indexProfile.fetch(indexProfile);
+    assert.equal(profile.mintAmount[0].toNumber(), 0);
+    assert.equal(profile.mintAmount[1].toNumber(), 0);
+    const shares = await connection.getTokenAccountBalance(
+      getAssociatedTokenAddressSync(shareMint, wallet.publicKey)
+    );
+    // 9-decimal wSOL deposits mint one 6-decimal share per whole SOL
+    assert.equal(shares.value.amount, deposited.divn(1_000).toString());
+    assert.isNull(await connection.getAccountInfo(deposit));
+  });
+
+  it("Burns shares for their share of the holdings", async () => {
+    const ownerShares = getAssociatedTokenAddressSync(
+      shareMint,
+      wallet.publicKey
+    );
+    const { value } = await connection.getTokenAccountBalance(ownerShares);
+    const half = new anchor.BN(value.amount).divn(2);
+
+    await program.methods
+      .redeemShares(half)
+      .accounts({
+        owner: wallet.publicKey,
+        payer: wallet.publicKey,
+        config,
+        index,
+        indexProfile,
+        shareMint,
+        ownerShares,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const profile = await program.account.indexProfile.fetch(indexProfile);
+    assert.equal(profile.mintAmount[0].toNumber(), 500_000);
+    assert.equal(profile.mintAmount[1].toNumber(), 250_000);
+  });
+
+  const redeem = (mint: PublicKey, redeemAmount: number) =>
+    program.methods
+      .redeem(new anchor.BN(redeemAmount))
+      .accounts({
+        owner: wallet.publicKey,
+        payer: wallet.publicKey,
+        config,
+        index,
+        indexProfile,
+        mint,
+        indexToken: getAssociatedTokenAddressSync(mint, index, true),
+        ownerToken: getAssociatedTokenAddressSync(mint, wallet.publicKey),
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      });
+
+  it("Redeems part of a constituent", async () => {
+    await redeem(mintList[0], 400_000).rpc();
+
+    const profile = await program.account.indexProfile.fetch(indexProfile);
+    assert.equal(profile.mintAmount[0].toNumber(), 100_000);
+    const vault = await connection.getTokenAccountBalance(
+      getAssociatedTokenAddressSync(mintList[0], index, true)
+    );
+    assert.equal(vault.value.amount, "600000");
+  });
+
+  it("Refuses to redeem more than the profile holds", async () => {
+    try {
+      await redeem(mintList[0], 100_001).rpc();
+      assert.fail("redeem should be capped by the profile balance");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "InsufficientProfileBalance"
+      );
+    }
+  });
+
+  it("Redeems the rest of the basket in one transaction", async () => {
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        await redeem(mintList[0], 100_000).instruction(),
+        await redeem(mintList[1], 250_000).instruction()
+      )
+    );
+
+    const profile = await program.account.indexProfile.fetch(indexProfile);
+    assert.equal(profile.mintAmount[0].toNumber(), 0);
+    assert.equal(profile.mintAmount[1].toNumber(), 0);
+  });
+
+  it("Accumulates profile balances across deposits", async () => {
+    for (const depositSeed of [5, 6].map((n) => new anchor.BN(n))) {
+      const depositAccount = depositFor(depositSeed);
+      await depositWith(depositSeed).rpc();
+      await swapLeg(mintList[0], legAmount, 300_000, depositAccount);
+    }
+
+    const profile = await program.account.indexProfile.fetch(indexProfile);
+    assert.equal(profile.mintAmount[0].toNumber(), 600_000);
+    assert.equal(profile.mintAmount[1].toNumber(), 0);
+  });
+
+  it("Runs create → deposit → swap → finalize end to end", async () => {
+    const depositSeed = new anchor.BN(20);
+    const depositAccount = depositFor(depositSeed);
+    const balance = async (tokenAccount: PublicKey) =>
+      new anchor.BN(
+        (await connection.getTokenAccountBalance(tokenAccount)).value.amount
+      );
+    const vaults = mintList.map((mint) =>
+      getAssociatedTokenAddressSync(mint, index, true)
+    );
+    const jupiterSource = getAssociatedTokenAddressSync(
+      NATIVE_MINT,
+      jupiterAuthority,
+      true
+    );
+    const ownerShares = getAssociatedTokenAddressSync(
+      shareMint,
+      wallet.publicKey
+    );
+
+    const vaultsBefore = await Promise.all(vaults.map(balance));
+    const sourceBefore = await balance(jupiterSource);
+    const sharesBefore = await balance(ownerShares);
+
+    await depositWith(depositSeed).rpc();
+    const { amount: deposited } = await program.account.depositAccount.fetch(
+      depositAccount
+    );
+    await swapLeg(mintList[0], legAmount, 120_000, depositAccount);
+    await swapLeg(
+      mintList[1],
+      deposited.sub(legAmount),
+      80_000,
+      depositAccount
+    );
+
+    // Jupiter took the whole deposit and both legs landed in the vaults
+    assert.equal(
+      (await balance(jupiterSource)).sub(sourceBefore).toString(),
+      deposited.toString()
+    );
+    const vaultsAfter = await Promise.all(vaults.map(balance));
+    assert.equal(vaultsAfter[0].sub(vaultsBefore[0]).toNumber(), 120_000);
+    assert.equal(vaultsAfter[1].sub(vaultsBefore[1]).toNumber(), 80_000);
+
+    // Every ticket was consumed, and the wrapped deposit minted shares
+    for (const mint of mintList) {
+      assert.isNull(
+        await connection.getAccountInfo(ticketFor(mint, depositAccount))
+      );
+    }
+    assert.isNull(await connection.getAccountInfo(depositAccount));
+    assert.isTrue((await balance(ownerShares)).gt(sharesBefore));
+  });
+
+  it("Redeems a constituent to USDC through Jupiter", async () => {
+    // A separate keeper, so the USDC visibly moves from it to the owner
+    const redeemer = Keypair.generate();
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: wallet.publicKey,
+          toPubkey: redeemer.publicKey,
+          lamports: 2 * LAMPORTS_PER_SOL,
+        })
+      )
+    );
+    await bondKeeper(LAMPORTS_PER_SOL, redeemer);
+    const redeemerUsdc = (
+      await getOrCreateAssociatedTokenAccount(
+        connection,
+        wallet.payer,
+        USDC_MINT,
+        redeemer.publicKey
+      )
+    ).address;
+    const ownerUsdc = getAssociatedTokenAddressSync(
+      USDC_MINT,
+      wallet.publicKey
+    );
+
+    const redeemAmount = new anchor.BN(200_000);
+    const quotedOutAmount = new anchor.BN(300_000);
+    const initializeRedeemSwap = () =>
+      program.methods
+        .initializeRedeemSwap(redeemAmount)
+        .accounts({
+          owner: wallet.publicKey,
+          swapper: redeemer.publicKey,
+          payer: wallet.publicKey,
+          config,
+          index,
+          keeper: keeperFor(redeemer.publicKey),
+          indexProfile,
+          mint: mintList[0],
+          indexToken: getAssociatedTokenAddressSync(mintList[0], index, true),
+          swapperToken: getAssociatedTokenAddressSync(
+            mintList[0],
+            redeemer.publicKey
+          ),
+          usdc: USDC_MINT,
+          instructionsSysvarProgram: SYSVAR_INSTRUCTIONS_PUBKEY,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+    const swapToUsdc = (destination = redeemerUsdc) =>
+      sharedAccountsRoute(
+        USDC_MINT,
+        redeemAmount,
+        quotedOutAmount,
+        50,
+        destination,
+        mintList[0],
+        redeemer.publicKey
+      );
+    const finalizeRedeem = (payout: anchor.BN) =>
+      program.methods
+        .finalizeRedeem(payout)
+        .accounts({
+          swapper: redeemer.publicKey,
+          owner: wallet.publicKey,
+          payer: wallet.publicKey,
+          usdc: USDC_MINT,
+          swapperToken: redeemerUsdc,
+          ownerToken: ownerUsdc,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+
+    for (const [instructions, code] of [
+      [
+        [await initializeRedeemSwap(), swapToUsdc()],
+        "MissingFinalizeRedeemIx",
+      ],
+      [
+        [
+          await initializeRedeemSwap(),
+          swapToUsdc(),
+          await finalizeRedeem(quotedOutAmount.subn(1)),
+        ],
+        "InvalidFinalizeRedeemAmount",
+      ],
+      [
+        [
+          await initializeRedeemSwap(),
+          swapToUsdc(ownerUsdc),
+          await finalizeRedeem(quotedOutAmount),
+        ],
+        "InvalidSwapDestination",
+      ],
+    ] as [TransactionInstruction[], string][]) {
+      try {
+        await provider.sendAndConfirm(
+          new Transaction().add(...instructions),
+          [redeemer]
+        );
+        assert.fail(`expected ${code}`);
+      } catch (err) {
+        assert.include((err as { logs: string[] }).logs.join("\n"), code);
+      }
+    }
+
+    const { mintAmount: before } = await program.account.indexProfile.fetch(
+      indexProfile
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        await initializeRedeemSwap(),
+        swapToUsdc(),
+        await finalizeRedeem(quotedOutAmount)
+      ),
+      [redeemer]
+    );
+
+    const { mintAmount: after } = await program.account.indexProfile.fetch(
+      indexProfile
+    );
+    assert.equal(before[0].sub(after[0]).toString(), redeemAmount.toString());
+    const { value } = await connection.getTokenAccountBalance(ownerUsdc);
+    assert.equal(value.amount, quotedOutAmount.toString());
+    const redeemerBalance = await connection.getTokenAccountBalance(
+      redeemerUsdc
+    );
+    assert.equal(redeemerBalance.value.amount, "0");
+  });
+
+  const setMaxSlippage = (authority: Keypair, maxSlippageBps: number) =>
+    program.methods
+      .setIndexMaxSlippage(maxSlippageBps)
+      .accounts({ authority: authority.publicKey, config, index })
+      .signers([authority]);
+
+  it("Lets only the curator set the index max slippage", async () => {
+    try {
+      await setMaxSlippage(wallet.payer, 100).rpc();
+      assert.fail("set_index_max_slippage should be gated by the curator");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "PrivilageEscalated"
+      );
+    }
+
+    await setMaxSlippage(admin, 100).rpc();
+    const account = await program.account.indexAccount.fetch(index);
+    assert.equal(account.maxSlippageBps, 100);
+  });
+
+  it("Enforces the tighter of the index and deposit slippage", async () => {
+    const looseSeed = new anchor.BN(7);
+    await depositWith(looseSeed).rpc();
+    await swapLeg(mintList[0], legAmount, 100_000, depositFor(looseSeed), {
+      slippageBps: 30,
+    });
+
+    const tightSeed = new anchor.BN(8);
+    await depositWith(tightSeed, false, 20).rpc();
+    try {
+      await swapLeg(mintList[0], legAmount, 100_000, depositFor(tightSeed), {
+        slippageBps: 30,
+      });
+      assert.fail("the route exceeds the depositor's max slippage");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "InvalidSlippage"
+      );
+    }
+  });
+
+  it("Refuses swaps that pay out to someone else", async () => {
+    const depositSeed = new anchor.BN(7);
+    const depositAccount = depositFor(depositSeed);
+    const { amount: deposited } = await program.account.depositAccount.fetch(
+      depositAccount
+    );
+    const inAmount = deposited.sub(legAmount);
+
+    try {
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          await initializeSwap(
+            mintList[1],
+            inAmount,
+            depositAccount
+          ).instruction(),
+          sharedAccountsRoute(
+            mintList[1],
+            inAmount,
+            new anchor.BN(100_000),
+            50,
+            getAssociatedTokenAddressSync(mintList[1], admin.publicKey)
+          ),
+          await finalize(mintList[1], depositAccount).instruction()
+        )
+      );
+      assert.fail("the swap should pay into the swapper's token account");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "InvalidSwapDestination"
+      );
+    }
+  });
+
+  it("Refunds what an exact-out swap leaves unspent", async () => {
+    const depositSeed = new anchor.BN(17);
+    const depositAccount = depositFor(depositSeed);
+    await depositWith(depositSeed).rpc();
+    const depositToken = getAssociatedTokenAddressSync(
+      NATIVE_MINT,
+      depositAccount,
+      true
+    );
+    const exactOutLeg = async (quotedInAmount: anchor.BN) => {
+      await mintTo(
+        connection,
+        wallet.payer,
+        mintList[0],
+        getAssociatedTokenAddressSync(mintList[0], jupiterAuthority, true),
+        wallet.publicKey,
+        100_000
+      );
+      await provider.sendAndConfirm(
+        new Transaction().add(
+          await initializeSwap(
+            mintList[0],
+            legAmount,
+            depositAccount
+          ).instruction(),
+          sharedAccountsExactOutRoute(
+            mintList[0],
+            new anchor.BN(100_000),
+            quotedInAmount,
+            50
+          ),
+          await finalize(mintList[0], depositAccount).instruction()
+        )
+      );
+    };
+
+    // With its slippage, the swap could spend more than the leg
+    try {
+      await exactOutLeg(legAmount);
+      assert.fail("the quoted input doesn't fit in the leg");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "InvalidAmount"
+      );
+    }
+
+    // Buying the leg for 90% of it leaves the other 10% in the deposit
+    const quotedInAmount = legAmount.muln(9).divn(10);
+    await exactOutLeg(quotedInAmount);
+    const { outAmounts, amount: deposited } =
+      await program.account.depositAccount.fetch(depositAccount);
+    assert.equal(outAmounts[0].toNumber(), 100_000);
+    const unspent = legAmount.sub(quotedInAmount).toString();
+    const { value } = await connection.getTokenAccountBalance(depositToken);
+    assert.equal(value.amount, unspent);
+
+    // The shares get minted with the last leg, and the deposit stays open for
+    // its owner to refund what's left
+    await swapLeg(
+      mintList[1],
+      deposited.sub(legAmount),
+      50_000,
+      depositAccount
+    );
+    const left = await connection.getTokenAccountBalance(depositToken);
+    assert.equal(left.value.amount, unspent);
+
+    await setIndexPause({ redeemOnly: {} });
+    try {
+      await refund(depositAccount);
+    } finally {
+      await setIndexPause({ active: {} });
+    }
+    assert.isNull(await connection.getAccountInfo(depositAccount));
+  });
+
+  it("Restricts swaps to the index AMM allowlist", async () => {
+    const setAmmAllowlist = (allowedAmms: number[], maxHops = 4) =>
+      program.methods
+        .setIndexAmmAllowlist(Buffer.from(allowedAmms), maxHops)
+        .accounts({
+          curator: admin.publicKey,
+          index,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    const depositSeed = new anchor.BN(9);
+    const depositAccount = depositFor(depositSeed);
+    await depositWith(depositSeed).rpc();
+
+    for (const [allowedAmms, maxHops] of [
+      [[RAYDIUM, RAYDIUM], 4],
+      [[255], 4],
+      [[RAYDIUM], 0],
+    ] as [number[], number][]) {
+      try {
+        await setAmmAllowlist(allowedAmms, maxHops);
+        assert.fail("the allowlist is invalid");
+      } catch (err) {
+        assert.include(
+          (err as { logs: string[] }).logs.join("\n"),
+          "InvalidAmmAllowlist"
+        );
+      }
+    }
+
+    await setAmmAllowlist([RAYDIUM]);
+    try {
+      await swapLeg(mintList[0], legAmount, 100_000, depositAccount);
+      assert.fail("the route goes through a Whirlpool");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "AmmNotAllowed"
+      );
+    }
+
+    await setAmmAllowlist([WHIRLPOOL, RAYDIUM_CLMM, METEORA_DLMM, PHOENIX]);
+    await swapLeg(mintList[0], legAmount, 100_000, depositAccount);
+    const account = await program.account.indexAccount.fetch(index);
+    assert.equal(account.allowedAmms.length, 4);
+  });
+
+  const setDepositMintOracle = (oracle: PublicKey | null) =>
+    program.methods
+      .setDepositMintOracle(NATIVE_MINT, oracle)
+      .accounts({ admin: admin.publicKey, config })
+      .signers([admin])
+      .rpc();
+
+  it("Guards swap quotes with the index oracles", async () => {
+    const priceGuard = { bandBps: 100, maxAgeSecs: 60, maxConfidenceBps: 50 };
+    const setPriceGuard = (oracles: PublicKey[], guard = priceGuard) =>
+      program.methods
+        .setIndexPriceGuard(oracles, guard)
+        .accounts({
+          curator: admin.publicKey,
+          index,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+    try {
+      await setPriceGuard([Keypair.generate().publicKey]);
+      assert.fail("there has to be one oracle per mint");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "InvalidOracleList"
+      );
+    }
+
+    const oracles = mintList.map(() => Keypair.generate().publicKey);
+    const inputOracle = Keypair.generate().publicKey;
+    await setPriceGuard(oracles);
+
+    const depositSeed = new anchor.BN(10);
+    const depositAccount = depositFor(depositSeed);
+    await depositWith(depositSeed).rpc();
+
+    // wSOL has no oracle yet, so the quote can't be priced
+    try {
+      await swapLeg(mintList[0], legAmount, 100_000, depositAccount);
+      assert.fail("the swap has no oracle prices");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "MissingOracle"
+      );
+    }
+
+    // Accounts that aren't Pyth prices are refused
+    await setDepositMintOracle(inputOracle);
+    try {
+      await swapLeg(mintList[0], legAmount, 100_000, depositAccount, {
+        oracles: { inputOracle, outputOracle: oracles[0] },
+      });
+      assert.fail("the oracle accounts aren't Pyth prices");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "InvalidOracle"
+      );
+    }
+
+    // The fixtures are older than a minute
+    const fixtures = { inputOracle: SOL_ORACLE, outputOracle: LEG_ORACLES[0] };
+    await setPriceGuard(LEG_ORACLES);
+    await setDepositMintOracle(SOL_ORACLE);
+    try {
+      await swapLeg(mintList[0], legAmount, 100_000, depositAccount, {
+        oracles: fixtures,
+      });
+      assert.fail("the oracle prices are stale");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "StalePrice"
+      );
+    }
+
+    // 1.2 SOL at $150 is worth 0.1 of the first mint at $1,800, a quote 10%
+    // short of that is refused and one at the oracle price goes through
+    await setPriceGuard(LEG_ORACLES, STATIC_PRICE_GUARD);
+    try {
+      await swapLeg(mintList[0], legAmount, 90_000, depositAccount, {
+        oracles: fixtures,
+      });
+      assert.fail("the quote is 10% below the oracle price");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "QuoteOutsidePriceBand"
+      );
+    }
+    await swapLeg(mintList[0], legAmount, 100_000, depositAccount, {
+      oracles: fixtures,
+    });
+    const { outAmounts } = await program.account.depositAccount.fetch(
+      depositAccount
+    );
+    assert.equal(outAmounts[0].toNumber(), 100_000);
+
+    await setPriceGuard([]);
+    await setDepositMintOracle(null);
+    const account = await program.account.indexAccount.fetch(index);
+    assert.isEmpty(account.oracles);
+  });
+
+  // The Mint, Vault and Oracle of every Mint of the index, which everything
+  // that values the index takes as remaining accounts
+  const navAccounts = (oracles: PublicKey[]) =>
+    []
+      .concat(
+        ...mintList.map((mint, i) => [
+          mint,
+          getAssociatedTokenAddressSync(mint, index, true),
+          oracles[i],
+        ])
+      )
+      .map((pubkey: PublicKey) => ({
+        pubkey,
+        isSigner: false,
+        isWritable: false,
+      }));
+  const setOracles = (oracles: PublicKey[], priceGuard = STATIC_PRICE_GUARD) =>
+    program.methods
+      .setIndexPriceGuard(oracles, priceGuard)
+      .accounts({
+        curator: admin.publicKey,
+        index,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+  it("Prices the index from its oracles", async () => {
+    const oracles = mintList.map(() => Keypair.generate().publicKey);
+    const legs = navAccounts(oracles);
+    const getNav = (remainingAccounts: typeof legs) =>
+      program.methods
+        .getNav()
+        .accounts({ index, shareMint })
+        .remainingAccounts(remainingAccounts);
+
+    try {
+      await getNav(legs).rpc();
+      assert.fail("the index has no oracles");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "NoOracles"
+      );
+    }
+
+    await setOracles(oracles);
+    for (const [remainingAccounts, code] of [
+      [legs.slice(3), "InvalidNavAccounts"],
+      [[...legs.slice(3), ...legs.slice(0, 3)], "InvalidNavAccounts"],
+      // The oracles aren't Pyth prices
+      [legs, "InvalidOracle"],
+    ] as [typeof legs, string][]) {
+      try {
+        await getNav(remainingAccounts).rpc();
+        assert.fail(`expected ${code}`);
+      } catch (err) {
+        assert.equal((err as anchor.AnchorError).error.errorCode.code, code);
+      }
+    }
+
+    // Every vault at its oracle price, with 6 decimals like the prices are
+    // quoted in: the mints have 6 decimals and the prices an exponent of -8
+    await setOracles(LEG_ORACLES);
+    const nav = await getNav(navAccounts(LEG_ORACLES)).view();
+    const prices = [180_000_000_000, 240_000_000_000];
+    const valueOf = (amounts: anchor.BN[]) =>
+      amounts.reduce(
+        (total, amount, i) => total.add(amount.muln(prices[i] / 1e8)),
+        new anchor.BN(0)
+      );
+    const vaults = await Promise.all(
+      mintList.map(async (mint) => {
+        const vault = getAssociatedTokenAddressSync(mint, index, true);
+        const { value } = await connection.getTokenAccountBalance(vault);
+        return new anchor.BN(value.amount);
+      })
+    );
+    const { backing, pendingFeeShares } =
+      await program.account.indexAccount.fetch(index);
+    const minted = (await connection.getTokenSupply(shareMint)).value.amount;
+    const supply = new anchor.BN(minted).add(pendingFeeShares);
+
+    assert.equal(nav.totalValue.toString(), valueOf(vaults).toString());
+    assert.equal(nav.shareValue.toString(), valueOf(backing).toString());
+    assert.isTrue(nav.shareValue.gtn(0));
+    assert.equal(
+      nav.valuePerShare.toString(),
+      nav.shareValue.muln(1_000_000).div(supply).toString()
+    );
+
+    await setOracles([]);
+  });
+
+  const fees = {
+    recipient: admin.publicKey,
+    managementFeeBps: 0,
+    performanceFeeBps: 0,
+    entryFeeBps: 100,
+    exitFeeBps: 0,
+  };
+  const setFees = (fees, curator = admin, legs = []) =>
+    program.methods
+      .setIndexFees(fees)
+      .accounts({ curator: curator.publicKey, index, shareMint })
+      .remainingAccounts(legs)
+      .signers([curator])
+      .rpc();
+  const recipientShares = getAssociatedTokenAddressSync(
+    shareMint,
+    admin.publicKey
+  );
+  const collectFees = (legs = []) =>
+    program.methods
+      .collectFees({ shares: {} })
+      .accounts({
+        recipient: admin.publicKey,
+        config,
+        index,
+        recipientProfile: PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("profile"),
+            index.toBuffer(),
+            admin.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0],
+        shareMint,
+        recipientShares,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+  it("Charges the curator's fees in shares", async () => {
+    for (const [attempt, code] of [
+      [() => setFees(fees, Keypair.generate()), "PrivilageEscalated"],
+      [() => setFees({ ...fees, exitFeeBps: 10_001 }), "InvalidFees"],
+      // The index has no oracles to measure a performance against
+      [
+        () => setFees({ ...fees, performanceFeeBps: 1_000 }),
+        "PerformanceFeeWithoutOracles",
+      ],
+    ] as [() => Promise<string>, string][]) {
+      try {
+        await attempt();
+        assert.fail(`expected ${code}`);
+      } catch (err) {
+        assert.equal((err as anchor.AnchorError).error.errorCode.code, code);
+      }
+    }
+    await setFees(fees);
+
+    const depositSeed = new anchor.BN(11);
+    const depositAccount = depositFor(depositSeed);
+    await depositWith(depositSeed).rpc();
+    const { amount: deposited } = await program.account.depositAccount.fetch(
+      depositAccount
+    );
+    await swapLeg(mintList[0], legAmount, 100_000, depositAccount);
+    await swapLeg(
+      mintList[1],
+      deposited.sub(legAmount),
+      50_000,
+      depositAccount
+    );
+
+    // 1% of the deposit's shares is held back for the recipient
+    const { pendingFeeShares } = await program.account.indexAccount.fetch(
+      index
+    );
+    assert.isTrue(pendingFeeShares.gtn(0));
+
+    await collectFees();
+    const { value } = await connection.getTokenAccountBalance(recipientShares);
+    assert.equal(value.amount, pendingFeeShares.toString());
+    const account = await program.account.indexAccount.fetch(index);
+    assert.isTrue(account.pendingFeeShares.isZero());
+
+    try {
+      await collectFees();
+      assert.fail("everything was collected");
+    } catch (err) {
+      assert.equal((err as anchor.AnchorError).error.errorCode.code, "NoFees");
+    }
+
+    await setFees({ ...fees, entryFeeBps: 0 });
+  });
+
+  it("Charges a performance fee on what the NAV gains", async () => {
+    const performanceFees = {
+      ...fees,
+      performanceFeeBps: 2_000,
+      entryFeeBps: 0,
+    };
+    const getNav = (oracles: PublicKey[]) =>
+      program.methods
+        .getNav()
+        .accounts({ index, shareMint })
+        .remainingAccounts(navAccounts(oracles))
+        .view();
+    const unit = new anchor.BN(1_000_000);
+    const supplyOf = async () => {
+      const { value } = await connection.getTokenSupply(shareMint);
+      const { pendingFeeShares } = await program.account.indexAccount.fetch(
+        index
+      );
+      return new anchor.BN(value.amount).add(pendingFeeShares);
+    };
+
+    // Turning the fee on starts the high-water mark at today's NAV per share
+    await setOracles(LEG_ORACLES);
+    await setFees(performanceFees, admin, navAccounts(LEG_ORACLES));
+    const supply = await supplyOf();
+    const { shareValue: startValue } = await getNav(LEG_ORACLES);
+    const { highWaterMark } = await program.account.indexAccount.fetch(index);
+    assert.equal(
+      highWaterMark.toString(),
+      startValue.mul(unit).div(supply).toString()
+    );
+
+    // Leg A goes from $1,800 to $2,700, the curator takes 20% of the gain
+    const risen = [LEG_A_UP_ORACLE, LEG_ORACLES[1]];
+    await setOracles(risen);
+    const { shareValue } = await getNav(risen);
+    assert.isTrue(shareValue.gt(startValue));
+    const navPerShare = shareValue.mul(unit).div(supply);
+    const gain = navPerShare.sub(highWaterMark).mul(supply).div(unit);
+    const feeValue = gain.muln(2_000).divn(10_000);
+    const feeShares = feeValue.mul(supply).div(shareValue.sub(feeValue));
+
+    await setFees(performanceFees, admin, navAccounts(risen));
+    const { pendingFeeShares } = await program.account.indexAccount.fetch(
+      index
+    );
+    assert.isTrue(feeShares.gtn(0));
+    assert.equal(pendingFeeShares.toString(), feeShares.toString());
+
+    // Nothing more is owed until the NAV climbs past the new mark
+    await setFees(performanceFees, admin, navAccounts(risen));
+    const account = await program.account.indexAccount.fetch(index);
+    assert.equal(account.pendingFeeShares.toString(), feeShares.toString());
+
+    await collectFees(navAccounts(risen));
+    await setFees({ ...fees, entryFeeBps: 0 }, admin, navAccounts(risen));
+    await setOracles([]);
+  });
+
+  it("Pays keepers a bounty for every settled leg", async () => {
+    const setKeeperBounty = (keeperBounty: object) =>
+      program.methods
+        .setIndexKeeperBounty(keeperBounty as any)
+        .accounts({ authority: admin.publicKey, config, index })
+        .signers([admin])
+        .rpc();
+
+    try {
+      await setKeeperBounty({ bps: { bps: 10_001 } });
+      assert.fail("the cut can't exceed the leg");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "InvalidKeeperBounty"
+      );
+    }
+
+    // Lamports are reserved on the deposit and paid out leg by leg
+    const perLeg = 1_000_000;
+    await setKeeperBounty({ lamports: { perLeg: new anchor.BN(perLeg) } });
+    const lamportDeposit = depositFor(new anchor.BN(12));
+    await depositWith(new anchor.BN(12)).rpc();
+    const reserved = await connection.getBalance(lamportDeposit);
+
+    await swapLeg(mintList[0], legAmount, 100_000, lamportDeposit);
+    assert.equal(
+      await connection.getBalance(lamportDeposit),
+      reserved - perLeg
+    );
+
+    // A cut of the leg stays with the keeper
+    await setKeeperBounty({ bps: { bps: 1_000 } });
+    const cutDeposit = depositFor(new anchor.BN(13));
+    await depositWith(new anchor.BN(13)).rpc();
+    await swapLeg(mintList[0], legAmount, 100_000, cutDeposit);
+    const { outAmounts } = await program.account.depositAccount.fetch(
+      cutDeposit
+    );
+    assert.equal(outAmounts[0].toNumber(), 90_000);
+
+    // USDC is escrowed for the deposit and paid out leg by leg, the payer
+    // funds it out of the USDC the redemption test paid out
+    const usdcPerLeg = 10_000;
+    await setKeeperBounty({ usdc: { perLeg: new anchor.BN(usdcPerLeg) } });
+    try {
+      await depositWith(new anchor.BN(16)).rpc();
+      assert.fail("the USDC bounty needs an escrow");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "InvalidBountyAccounts"
+      );
+    }
+    const usdcDeposit = depositFor(new anchor.BN(16));
+    const walletUsdc = getAssociatedTokenAddressSync(
+      USDC_MINT,
+      wallet.publicKey
+    );
+    const usdcBefore = (await connection.getTokenAccountBalance(walletUsdc))
+      .value.amount;
+    await depositWith(new anchor.BN(16), false, null, true).rpc();
+    const bountyToken = bountyFor(usdcDeposit);
+    const escrowed = await connection.getTokenAccountBalance(bountyToken);
+    assert.equal(escrowed.value.amount, (2 * usdcPerLeg).toString());
+
+    const { amount: usdcDeposited } =
+      await program.account.depositAccount.fetch(usdcDeposit);
+    await swapLeg(mintList[0], legAmount, 100_000, usdcDeposit, {
+      usdcBounty: true,
+    });
+    const left = await connection.getTokenAccountBalance(bountyToken);
+    assert.equal(left.value.amount, usdcPerLeg.toString());
+
+    // The last leg empties and closes the escrow, the wallet swapped both legs
+    await swapLeg(
+      mintList[1],
+      usdcDeposited.sub(legAmount),
+      50_000,
+      usdcDeposit,
+      { usdcBounty: true }
+    );
+    assert.isNull(await connection.getAccountInfo(bountyToken));
+    const usdcAfter = (await connection.getTokenAccountBalance(walletUsdc))
+      .value.amount;
+    assert.equal(usdcAfter, usdcBefore);
+
+    await setKeeperBounty({ none: {} });
+  });
+
+  it("Slashes a keeper for a swap below the oracle price band", async () => {
+    const slashKeeper = (dispute: PublicKey, lamports: anchor.BN) =>
+      program.methods
+        .slashKeeper(lamports)
+        .accounts({
+          admin: admin.publicKey,
+          owner: wallet.publicKey,
+          payer: wallet.publicKey,
+          config,
+          keeper,
+          dispute,
+          depositMint: null,
+          depositOracle: null,
+          wsolOracle: null,
+        })
+        .signers([admin])
+        .rpc();
+    const oracles = { inputOracle: SOL_ORACLE, outputOracle: LEG_ORACLES[0] };
+    await setOracles(LEG_ORACLES);
+    await setDepositMintOracle(SOL_ORACLE);
+
+    // Slipping 0.5% under the oracle price is within the band, so there's
+    // nothing to dispute and finalize closes the ticket
+    const honestDeposit = depositFor(new anchor.BN(14));
+    await depositWith(new anchor.BN(14)).rpc();
+    const honest = { oracles, openDispute: true };
+    try {
+      await swapLeg(mintList[0], legAmount, 99_500, honestDeposit, honest);
+      assert.fail("the swap landed within the band");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "UnwarrantedDispute"
+      );
+    }
+    await swapLeg(mintList[0], legAmount, 99_500, honestDeposit, { oracles });
+    assert.isNull(
+      await connection.getAccountInfo(ticketFor(mintList[0], honestDeposit))
+    );
+
+    // A quote at the edge of the band less a 20 bps platform fee delivers
+    // 98_802 of the 99_000 the band asks for, and has to open a dispute
+    const shortDeposit = depositFor(new anchor.BN(15));
+    await depositWith(new anchor.BN(15)).rpc();
+    const short = { oracles, platformFeeBps: 20 };
+    try {
+      await swapLeg(mintList[0], legAmount, 99_000, shortDeposit, short);
+      assert.fail("the swap landed below the band");
+    } catch (err) {
+      assert.include(
+        (err as { logs: string[] }).logs.join("\n"),
+        "MissingDispute"
+      );
+    }
+    const dispute = await swapLeg(
+      mintList[0],
+      legAmount,
+      99_000,
+      shortDeposit,
+      { ...short, openDispute: true }
+    );
+    assert.isNull(
+      await connection.getAccountInfo(ticketFor(mintList[0], shortDeposit))
+    );
+
+    // 198 units of the first mint at $1,800 are 0.002376 SOL at $150
+    const { shortfall } = await program.account.swapDispute.fetch(dispute);
+    assert.equal(shortfall.toNumber(), 2_376_000);
+
+    try {
+      await slashKeeper(dispute, shortfall.addn(1));
+      assert.fail("the slash exceeds the shortfall");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "SlashExceedsShortfall"
+      );
+    }
+
+    const { bond } = await program.account.keeperAccount.fetch(keeper);
+    await slashKeeper(dispute, shortfall);
+    const account = await program.account.keeperAccount.fetch(keeper);
+    assert.equal(account.bond.toString(), bond.sub(shortfall).toString());
+    assert.equal(account.slashed.toString(), shortfall.toString());
+    assert.isNull(await connection.getAccountInfo(dispute));
+
+    await setOracles([]);
+    await setDepositMintOracle(null);
+
+    // The bond stays locked through the dispute window of the last swap
+    try {
+      await program.methods
+        .withdrawKeeperBond(new anchor.BN(1_000))
+        .accounts({ authority: wallet.publicKey, config, keeper })
+        .rpc();
+      assert.fail("the bond is still locked");
+    } catch (err) {
+      assert.equal(
+        (err as anchor.AnchorError).error.errorCode.code,
+        "BondLocked"
+      );
+    }
   });
 });